@@ -2,7 +2,6 @@ import Tile from './Tile.js';
 import './board.css'
 import { useEffect, useState} from 'react';
 import { Button, MenuItem, Select } from '@material-ui/core';
-import { Solver, GameBoard } from './Solver.js';
 
 
 export function Game(props) {
@@ -12,11 +11,12 @@ export function Game(props) {
     // Handler for the wasm functions
     const [backend, setBackend] = useState(null);
 
-    // Track original board so 'Reset' functionality works
-    const [originalBoard, setOriginalBoard] = useState([1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,0]);
-
-    // Track current board
-    const [numbers, setNumbers]   = useState([]);
+    // The game itself (board, move history, timer) lives in the wasm GameSession, these
+    // mirror what it holds so React knows when to re-render
+    const [game, setGame]           = useState(null);
+    const [numbers, setNumbers]     = useState([]);
+    const [moveCount, setMoveCount] = useState(0);
+    const [elapsed, setElapsed]     = useState(0);
 
     // Track the solution to the current board
     const [solution, setSolution] = useState(null);
@@ -24,36 +24,57 @@ export function Game(props) {
     // For solving logic, track which move in the solution list we are at
     const [solMove, setSolMove]   = useState(0);
 
-    // Determine if the current move is valid. A valid move is from any
-    // tile adjacent to the empty square
-    const isMoveValid = (index, zero_index) => {
-
-                // Move up
-        return ((zero_index > size-1 && zero_index - size === index) ||
-                // Move down
-                (Math.floor(zero_index / size) < size - 1 && zero_index + size === index) ||
-                // Move left
-                (zero_index % size > 0 && zero_index - 1 === index) ||
-                // Move right
-                (zero_index % size < size - 1 && zero_index + 1 === index))
+    // The solver runs a slice at a time so the page stays responsive, track the running
+    // session, how many nodes it has expanded so far and its f bound
+    const [session, setSession]   = useState(null);
+    const [progress, setProgress] = useState(0);
+    const [fBound, setFBound]     = useState(null);
+
+    // Last hint the player asked for, cleared whenever the board changes
+    const [hint, setHint] = useState(null);
+
+    // Copy the game's state into React after it changes
+    const refresh = (current) => {
+        setNumbers(Array.from(current.tiles));
+        setMoveCount(current.move_count);
+        setElapsed(current.elapsed_ms);
+        setHint(null);
     }
 
-    // Callback for when a user clicks a tile
-    const moveTile = (index) => {
-        const value      = numbers[index];
-        const zero_index = numbers.findIndex(entry => entry === 0);
+    // Start playing the given tiles, throwing away the previous game
+    const startGame = (module, newSize, tiles) => {
+        const newGame = new module.GameSession({ size: newSize, tiles: tiles });
 
-        if (isMoveValid(index, zero_index)) {
-            let new_numbers = numbers.map(value => value);
+        if (game !== null) {
+            game.free();
+        }
+        setGame(newGame);
+        setSolution(null);
+        setSolMove(0);
+        refresh(newGame);
+    }
 
-            new_numbers[index] = 0;
-            new_numbers[zero_index] = value;
-            console.log(new_numbers);
+    // Callback for when a user clicks a tile. Any tile in line with the empty
+    // square can be clicked, the tiles between slide along with it. Ignored while
+    // the solver is running, its solution is for the board it started from
+    const moveTile = (index) => {
+        if (session !== null) { return; }
 
-            setNumbers(new_numbers);
+        if (game.click_tile(index) > 0) {
+            refresh(game);
         }
     }
 
+    const undo = () => {
+        game.undo();
+        refresh(game);
+    }
+
+    const redo = () => {
+        game.redo();
+        refresh(game);
+    }
+
     const updateSize = (event) => {
         // Ignore if no change in size
         if (size === event.target.value) { return; }
@@ -64,47 +85,101 @@ export function Game(props) {
 
     // Callback when user requests a new game
     const newGame = (newSize) => {
-        const board = backend.generate(4);
-        const state = Array.from(board);
         setSize(newSize);
-        setOriginalBoard(board);
-        setSolution(null);
-        setSolMove(0);
-        setNumbers(state.map(entry => entry));
+        startGame(backend, newSize, backend.generate(newSize));
     }
 
     // Callback when user requests to reset current game
     const reset = () => {
         setSolution(null);
         setSolMove(0);
-        setNumbers(originalBoard.get_state().map(entry => entry));
+        game.reset();
+        refresh(game);
     }
 
     // Callback for when the user requests to have the current board solved
     const solveBoard = () => {
-        const start = new GameBoard(numbers, size, 0, null);
-
-        let goalState = [1, 2, 3, 4, 5, 6, 7, 8, 0];
-        if (size === 4) {
-            goalState = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
+        let newSession = null;
+        try {
+            newSession = new backend.SolverSession({ size: size, tiles: numbers });
+        }
+        catch (error) {
+            console.error(error.message);
+            return;
         }
-
-        const goal  = new GameBoard(goalState, size, 0, null, null);
-        const solver = new Solver();
-        //console.log(originalBoard.render());
 
         console.time("solver");
+        setProgress(0);
+        setSession(newSession);
+    }
 
-        // Rust Solver
-        let solution = backend.solve(originalBoard);
-        setSolution(solution);
-
-        // Javascript Solver
-        //setSolution(solver.solve(start, goal));
+    // Callback for when the user asks which tile to move next
+    const showHint = () => {
+        try {
+            const result     = backend.hint({ size: size, tiles: numbers });
+            const zero_index = numbers.findIndex(entry => entry === 0);
+            const offsets    = [-size, size, -1, 1];
+
+            // The hint says where the empty square goes, the player moves the tile that is there
+            const tile = result.best_move === undefined ? null : numbers[zero_index + offsets[result.best_move]];
+            setHint({ tile: tile, distance: result.distance, exact: result.exact });
+            result.free();
+        }
+        catch (error) {
+            console.error(error.message);
+        }
+    }
 
+    // Callback for when the user gives up waiting on the solver
+    const cancelSolve = () => {
+        session.cancel();
+        session.free();
+        setSession(null);
         console.timeEnd("solver");
     }
 
+    // While a solve is running, do one slice of the search per timeout so rendering and
+    // clicks still get a chance to run in between
+    useEffect(() => {
+        if (session === null) {
+            return;
+        }
+
+        const timer = setTimeout(() => {
+            // The progress and result are wasm objects too, copy what's needed and free them
+            const step = session.step(20000);
+            const { done, solved, nodes_expanded, f_bound } = step;
+            step.free();
+
+            if (done) {
+                if (solved) {
+                    const result = session.result();
+                    setSolution(Array.from(result.moves));
+                    result.free();
+                }
+                session.free();
+                setSession(null);
+                console.timeEnd("solver");
+            }
+            else {
+                setProgress(nodes_expanded);
+                setFBound(f_bound);
+            }
+        }, 0);
+
+        return () => clearTimeout(timer);
+    }, [session, progress]);
+
+    // Keep the clock ticking while the player is part way through a game
+    useEffect(() => {
+        if (game === null || moveCount === 0 || game.solved) {
+            return;
+        }
+
+        const timer = setInterval(() => setElapsed(game.elapsed_ms), 1000);
+        return () => clearInterval(timer);
+    }, [game, moveCount]);
+
     // Make this call on initial load to load the wasm module
     useEffect(() => {
 
@@ -116,11 +191,10 @@ export function Game(props) {
             //const board = [11, 15, 3, 12, 2, 8, 10, 1, 4, 6, 5, 14, 13, 7, 9, 0]
             //const board = [8, 5, 14, 15, 10, 3, 11, 1, 4, 2, 9, 6, 13, 12, 7, 0];
             const board = [15, 13, 12, 5, 0, 9, 14, 10, 3, 7, 6, 8, 2, 1, 11, 4];
-            const state = Array.from(board);
-            setOriginalBoard(board);
-            setNumbers(state.map(entry => entry));
+            startGame(module, 4, board);
         };
         load_wasm();
+        // eslint-disable-next-line react-hooks/exhaustive-deps
     }, []);
 
     useEffect(() => {
@@ -130,55 +204,40 @@ export function Game(props) {
 
             // Introduce a sleep between moves so user can see them happen
             const timer = setTimeout(() => {
-                const move       = solution[solMove];
-                const zero_index = numbers.findIndex(entry => entry === 0);
-                let   index      = 0;
-
-                // This logic is a little strange. From a players perspective, they are
-                // trying to move a square into the empty space.
-                // From the solver's perspective it is trying to move the empty square around.
-                // So here we just swap the index of the empty space with where we are trying to move it.
-                if (move === 0) {
-                    index = zero_index - size;
-                }
-                else if (move === 1) {
-                    index = zero_index + size
-                }
-                else if (move === 2) {
-                    index = zero_index - 1;
-                }
-                else {
-                    index = zero_index + 1;
-                }
-
-                let new_numbers = numbers.map(value => value);
-
-                new_numbers[zero_index] = new_numbers[index];
-                new_numbers[index] = 0;
-                console.log(new_numbers);
-
-                setNumbers(new_numbers);
+                // Solution moves say where the empty square goes, the game applies them directly
+                game.apply_move(solution[solMove]);
+                refresh(game);
                 setSolMove(solMove + 1);
             }, 500);
 
             return () => clearTimeout(timer);
         }
-    }, [numbers, solution, solMove, size]);
+        // eslint-disable-next-line react-hooks/exhaustive-deps
+    }, [game, solution, solMove]);
 
+    const seconds = Math.floor(elapsed / 1000);
 
     return <div>
         <h1>{props.name}</h1>
         <div className={"board-container"}>
             <div className={`board-${size}`}>
                 {numbers.map((value, index) => (
-                    <Tile key={value} value={value} index={index} size={numbers.length} handleClick={moveTile} /> 
+                    <Tile key={value} value={value} index={index} size={numbers.length} handleClick={moveTile} />
                 ))}
             </div>
         </div>
         <div className={"controls"}>
-            <Button variant='contained' onClick={() => newGame(size)}>New Game</Button>
-            <Button variant='contained' onClick={reset}>Reset</Button>
-            <Button variant='contained' onClick={solveBoard} disabled={solution !== null}>Solve</Button>
+            <Button variant='contained' onClick={() => newGame(size)} disabled={session !== null}>New Game</Button>
+            <Button variant='contained' onClick={reset} disabled={session !== null}>Reset</Button>
+            <Button variant='contained' onClick={undo} disabled={game === null || !game.can_undo || solution !== null || session !== null}>Undo</Button>
+            <Button variant='contained' onClick={redo} disabled={game === null || !game.can_redo || solution !== null || session !== null}>Redo</Button>
+            <Button variant='contained' onClick={solveBoard} disabled={solution !== null || session !== null}>Solve</Button>
+            <Button variant='contained' onClick={cancelSolve} disabled={session === null}>Stop</Button>
+            <Button variant='contained' onClick={showHint} disabled={backend === null || session !== null}>Hint</Button>
+            <span>Moves: {moveCount} Time: {Math.floor(seconds / 60)}:{String(seconds % 60).padStart(2, '0')}</span>
+            { game !== null && game.solved && moveCount > 0 && <span>Solved!</span> }
+            { hint !== null && <span>{hint.tile === null ? 'Solved!' : `Move ${hint.tile}, ${hint.exact ? '' : 'at least '}${hint.distance} moves left`}</span> }
+            { session !== null && <span>Searching... {progress} nodes{fBound != null && `, f bound ${fBound}`}</span> }
             { /*
             <label htmlFor='board-size-select'>Select Board Size</label>
             <Select id='board-size-select' value={size} onChange={updateSize}>
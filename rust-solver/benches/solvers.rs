@@ -0,0 +1,118 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use std::env;
+use std::sync::Arc;
+
+use slider_solver_lib::{Algorithm, Board, GoalDistance, HeuristicKind, Move, SolverConfig, bidirectional_solver, ida_star_solver, solve};
+use slider_solver_lib::pdb::AdditivePatterns;
+
+// Each set is a fixed seed plus a number of random (non-backtracking) moves away from the goal,
+// so every run benchmarks the exact same boards
+struct BoardSet {
+    name: &'static str,
+    size: usize,
+    seed: u64,
+    walk_length: usize,
+    count: usize
+}
+
+const BOARD_SETS: [BoardSet; 3] = [
+    BoardSet { name: "3x3",      size: 3, seed: 3, walk_length: 200, count: 10 },
+    BoardSet { name: "4x4-easy", size: 4, seed: 4, walk_length: 30,  count: 10 },
+    BoardSet { name: "4x4-hard", size: 4, seed: 5, walk_length: 120, count: 3 },
+];
+
+// Solved by pattern-ida-star with the databases from 'build-pdbs', found in the directory named by
+// SLIDER_PATTERNS (patterns-5x5 by default). The group is skipped when they haven't been built
+const PATTERN_SET: BoardSet = BoardSet { name: "5x5", size: 5, seed: 25, walk_length: 100, count: 5 };
+
+type Solver = fn(&Board, &Board) -> Vec<Move>;
+
+const SOLVERS: [(&str, Solver); 2] = [
+    ("bidirectional", bidirectional_solver),
+    ("ida-star", ida_star_solver),
+];
+
+fn generate_set(set: &BoardSet) -> Vec<Board> {
+    let mut rng = ChaCha8Rng::seed_from_u64(set.seed);
+    let goal = Board::goal_board(set.size);
+
+    (0..set.count).map(|_| Board::new(set.size, goal.random_walk(set.walk_length, &mut rng).state)).collect()
+}
+
+fn bench_heuristics(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heuristic");
+
+    for set in BOARD_SETS.iter() {
+        let boards = generate_set(set);
+        group.throughput(Throughput::Elements(boards.len() as u64));
+
+        let goal = Board::goal_board(set.size);
+        for kind in [HeuristicKind::Manhattan, HeuristicKind::LinearConflict].iter() {
+            let goal_distance = GoalDistance::with_kind(&goal, *kind);
+            group.bench_with_input(BenchmarkId::new(kind.name(), set.name), &boards, |b, boards| {
+                b.iter(|| {
+                    for board in boards {
+                        criterion::black_box(goal_distance.estimate(&board.state));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_solvers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+
+    for set in BOARD_SETS.iter() {
+        let boards = generate_set(set);
+        let goal   = Board::goal_board(set.size);
+
+        group.throughput(Throughput::Elements(boards.len() as u64));
+        for (name, solver) in SOLVERS.iter() {
+            group.bench_with_input(BenchmarkId::new(*name, set.name), &boards, |b, boards| {
+                b.iter(|| {
+                    for board in boards {
+                        criterion::black_box(solver(board, &goal));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_patterns(c: &mut Criterion) {
+    let dir = env::var("SLIDER_PATTERNS").unwrap_or_else(|_| "patterns-5x5".to_string());
+    let patterns = match AdditivePatterns::load(&[&dir]) {
+        Ok(patterns) => Arc::new(patterns),
+        Err(err) => {
+            eprintln!("skipping the pattern database benchmarks, {}", err);
+            return;
+        }
+    };
+
+    let mut group = c.benchmark_group("solve-patterns");
+    group.sample_size(10);
+
+    let boards = generate_set(&PATTERN_SET);
+    let goal   = Board::goal_board(PATTERN_SET.size);
+    let config = SolverConfig { algorithm: Algorithm::PatternIdaStar, patterns: Some(patterns), ..SolverConfig::default() };
+
+    group.throughput(Throughput::Elements(boards.len() as u64));
+    group.bench_with_input(BenchmarkId::new(Algorithm::PatternIdaStar.name(), PATTERN_SET.name), &boards, |b, boards| {
+        b.iter(|| {
+            for board in boards {
+                criterion::black_box(solve(board, &goal, &config).expect("5x5 boards are solvable"));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_heuristics, bench_solvers, bench_patterns);
+criterion_main!(benches);
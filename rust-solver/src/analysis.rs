@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use super::{Board, Move, SolverConfig, solve};
+use super::ranking::{rank_solvable, solvable_state_count, unrank_solvable};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Largest board whose whole state space is enumerated, 3x3 has 9!/2 = 181,440 reachable boards
+// and 4x4 over 10 trillion
+pub const MAX_ANALYSIS_SIZE: usize = 3;
+
+// Start of a saved table, followed by a version byte, the board size, the goal tiles and then one
+// distance byte per board
+const MAGIC: &[u8; 4] = b"SPDT";
+const VERSION: u8 = 1;
+
+// Entries the breadth first search hasn't reached yet
+const UNSEEN: u8 = u8::MAX;
+
+// The optimal distance to a goal of every board that can reach it, found by a breadth first
+// search back from the goal. Boards are indexed by ranking::rank_solvable, which numbers the
+// boards that can reach the standard goal. When the goal is in the other half of the state space
+// tiles 1 and 2 are swapped before ranking, that maps one half onto the other without changing
+// which moves connect which boards
+pub struct DistanceTable {
+    goal: Board,
+    swap_labels: bool,
+    distances: Vec<u8>
+}
+
+impl DistanceTable {
+    pub fn build(goal_board: &Board) -> Result<DistanceTable, String> {
+        goal_board.validate()?;
+        if goal_board.size > MAX_ANALYSIS_SIZE {
+            return Err(format!("only boards up to {}x{} can be analysed", MAX_ANALYSIS_SIZE, MAX_ANALYSIS_SIZE));
+        }
+
+        let count = solvable_state_count(goal_board.size).expect("small boards can be ranked") as usize;
+        let mut table = DistanceTable {
+            goal: Board::new(goal_board.size, goal_board.state.clone()),
+            swap_labels: !goal_board.is_solvable(),
+            distances: vec![UNSEEN; count]
+        };
+
+        let goal_index = table.index(&table.goal.state);
+        table.distances[goal_index] = 0;
+
+        let mut queue = VecDeque::new();
+        queue.push_back(goal_index);
+
+        while let Some(index) = queue.pop_front() {
+            let distance = table.distances[index];
+            let board = table.board_at(index);
+
+            for the_move in MOVES.iter() {
+                if let Some(child) = board.apply_move(*the_move) {
+                    let child_index = table.index(&child.state);
+                    if table.distances[child_index] == UNSEEN {
+                        table.distances[child_index] = distance + 1;
+                        queue.push_back(child_index);
+                    }
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    pub fn goal(&self) -> &Board {
+        &self.goal
+    }
+
+    // Number of boards that can reach the goal
+    pub fn board_count(&self) -> usize {
+        self.distances.len()
+    }
+
+    // Fewest moves from the board to the goal, None if it isn't a board that can reach the goal
+    pub fn distance(&self, board: &Board) -> Option<usize> {
+        if board.size != self.goal.size || board.validate().is_err() || !board.can_reach(&self.goal) {
+            return None;
+        }
+        Some(self.distances[self.index(&board.state)] as usize)
+    }
+
+    pub fn max_distance(&self) -> usize {
+        self.distances.iter().copied().max().unwrap_or(0) as usize
+    }
+
+    // How many boards are each number of moves from the goal, indexed by distance
+    pub fn histogram(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.max_distance() + 1];
+        for distance in &self.distances {
+            counts[*distance as usize] += 1;
+        }
+        counts
+    }
+
+    // Every board at the maximum distance from the goal
+    pub fn hardest(&self) -> Vec<Board> {
+        let max_distance = self.max_distance() as u8;
+        self.distances.iter().enumerate()
+            .filter(|(_, distance)| **distance == max_distance)
+            .map(|(index, _)| self.board_at(index))
+            .collect()
+    }
+
+    // Checks a table (ex. one loaded from a file) against a fresh breadth first search from its
+    // goal, describing the first board that differs
+    pub fn verify(&self) -> Result<(), String> {
+        let expected = DistanceTable::build(&self.goal)?;
+
+        match self.distances.iter().zip(&expected.distances).position(|(distance, expected)| distance != expected) {
+            Some(index) => {
+                let board = self.board_at(index);
+                Err(format!("board {:?} is recorded as {} moves from the goal, it is {}", board.state, self.distances[index], expected.distances[index]))
+            },
+            None => Ok(())
+        }
+    }
+
+    // Solves every stride-th board in the table with the configured solver, checking each path
+    // reaches the goal in exactly the table's distance. For cross-checking the optimal solvers,
+    // returns how many boards were checked or describes the first one that fails
+    pub fn crosscheck(&self, config: &SolverConfig, stride: usize) -> Result<usize, String> {
+        let mut checked = 0;
+
+        for index in (0..self.distances.len()).step_by(stride.max(1)) {
+            let board = self.board_at(index);
+            let path = solve(&board, &self.goal, config).map_err(|err| format!("{:?}: {}", board.state, err))?.path;
+
+            if board.apply_moves(&path).is_none_or(|end| end != self.goal) {
+                return Err(format!("{:?}: the {} solution doesn't reach the goal", board.state, config.algorithm.name()));
+            }
+            if path.len() != self.distances[index] as usize {
+                return Err(format!("{:?}: the {} solution is {} moves, the optimal is {}", board.state, config.algorithm.name(), path.len(), self.distances[index]));
+            }
+            checked += 1;
+        }
+
+        Ok(checked)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 2 + self.goal.state.len() + self.distances.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.goal.size as u8);
+        bytes.extend_from_slice(&self.goal.state);
+        bytes.extend_from_slice(&self.distances);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<DistanceTable, String> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("not a distance table".to_string());
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(format!("unsupported distance table version {}", bytes[MAGIC.len()]));
+        }
+
+        let size = bytes[MAGIC.len() + 1] as usize;
+        if !(2..=MAX_ANALYSIS_SIZE).contains(&size) {
+            return Err(format!("unsupported distance table size {}", size));
+        }
+
+        let goal_start = MAGIC.len() + 2;
+        let goal_end = goal_start + size * size;
+        let count = solvable_state_count(size).expect("small boards can be ranked") as usize;
+        if bytes.len() != goal_end + count {
+            return Err(format!("a {}x{} distance table should be {} bytes, got {}", size, size, goal_end + count, bytes.len()));
+        }
+
+        let goal = Board::new(size, bytes[goal_start..goal_end].to_vec());
+        goal.validate().map_err(|err| format!("invalid goal in distance table: {}", err))?;
+
+        Ok(DistanceTable {
+            swap_labels: !goal.is_solvable(),
+            goal,
+            distances: bytes[goal_end..].to_vec()
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|err| format!("unable to write {}: {}", path, err))
+    }
+
+    pub fn load(path: &str) -> Result<DistanceTable, String> {
+        let bytes = fs::read(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+        DistanceTable::from_bytes(&bytes)
+    }
+
+    fn index(&self, state: &[u8]) -> usize {
+        if self.swap_labels {
+            rank_solvable(&swap_labels(state), self.goal.size) as usize
+        }
+        else {
+            rank_solvable(state, self.goal.size) as usize
+        }
+    }
+
+    fn board_at(&self, index: usize) -> Board {
+        let state = unrank_solvable(index as u128, self.goal.size);
+        if self.swap_labels {
+            Board::new(self.goal.size, swap_labels(&state))
+        }
+        else {
+            Board::new(self.goal.size, state)
+        }
+    }
+}
+
+// Renames tile 1 as 2 and 2 as 1, which flips which goal the board can reach
+fn swap_labels(state: &[u8]) -> Vec<u8> {
+    state.iter().map(|tile| match tile {
+        1 => 2,
+        2 => 1,
+        _ => *tile
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_3x3_table_matches_the_known_facts() {
+        let table = DistanceTable::build(&Board::goal_board(3)).unwrap();
+
+        assert_eq!(table.board_count(), 181_440);
+        assert_eq!(table.max_distance(), 31);
+
+        let histogram = table.histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), 181_440);
+        assert_eq!(histogram[0], 1);
+        assert_eq!(histogram[31], 2);
+
+        let hardest = table.hardest();
+        assert_eq!(hardest.len(), 2);
+        for board in &hardest {
+            assert_eq!(table.distance(board), Some(31));
+        }
+    }
+}
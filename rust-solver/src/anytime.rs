@@ -0,0 +1,244 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use web_time::{Duration, Instant};
+
+use super::{Board, Move, SolveError, SolverConfig};
+use super::heuristics::GoalDistance;
+use super::solver::check_boards;
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Weight on the heuristic for the first, greedy solution, a good SolverConfig::epsilon for
+// anytime_solver. Higher finds something sooner but leaves more to improve afterwards
+pub const DEFAULT_ANYTIME_WEIGHT: f64 = 3.0;
+
+// How often (in nodes) the limits and the cancel token are checked
+const LIMIT_CHECK_INTERVAL: usize = 1024;
+
+// A solution found by anytime_solver, along with how close to optimal it is known to be
+#[derive(Clone, Debug)]
+pub struct Improvement {
+    pub path: Vec<Move>,
+
+    // No solution can be shorter than this
+    pub lower_bound: usize,
+
+    pub nodes_expanded: usize,
+    pub running_time: Duration
+}
+
+impl Improvement {
+    pub fn cost(&self) -> usize {
+        self.path.len()
+    }
+
+    // Most moves the path could be longer than the optimal one
+    pub fn gap(&self) -> usize {
+        self.cost() - self.lower_bound
+    }
+
+    pub fn is_optimal(&self) -> bool {
+        self.lower_bound >= self.cost()
+    }
+}
+
+// Anytime weighted A*. Searches with f = g + epsilon * h, which finds a first solution about as
+// quickly as the other weighted searches, then keeps going: every later node whose unweighted
+// g + h can't beat the best solution so far is dropped, and each shorter solution found is
+// passed to on_improvement. The smallest unweighted g + h left on the open list is a
+// lower bound on the optimal length, so the search ends on its own once it proves optimality.
+//
+// Uses the config's heuristic and epsilon (see DEFAULT_ANYTIME_WEIGHT), the algorithm is ignored.
+// on_improvement returns whether to keep looking. Searching also stops once one of the config's
+// limits is reached or its cancel token is set, checked every 1024 nodes. The time limit is the
+// usual way to bound it.
+// Returns the best solution with the tightest bound known when the search stopped, which may be
+// tighter than the one last reported, or None if it stopped before any solution
+pub fn anytime_solver<F>(start_board: &Board, goal_board: &Board, config: &SolverConfig, mut on_improvement: F) -> Result<Option<Improvement>, SolveError>
+    where F: FnMut(&Improvement) -> bool
+{
+    check_boards(start_board, goal_board)?;
+
+    let start_time = Instant::now();
+    let heuristic  = GoalDistance::with_kind(goal_board, config.heuristic);
+    let size       = start_board.size;
+
+    // Weighted f in thousandths so the open list can use integer keys
+    let weight = (config.epsilon.max(1.0) * 1000.0).round() as i64;
+    let weighted_f = |g: i32, h: i32| 1000 * g as i64 + weight * h as i64;
+
+    // Open list ordered by weighted f, then deepest first. Entries go stale when a board is
+    // reached again more cheaply, they are skipped when popped
+    let mut open: BinaryHeap<(Reverse<i64>, i32, i32, Vec<u8>)> = BinaryHeap::new();
+
+    // How many open list entries there are for each unweighted f, the first key is the bound
+    let mut open_f: BTreeMap<i32, usize> = BTreeMap::new();
+
+    // Cheapest known cost of each board reached, and the move that got there
+    let mut reached: HashMap<Vec<u8>, (i32, Move)> = HashMap::new();
+
+    let mut best: Option<Improvement> = None;
+    let mut nodes_expanded = 0;
+
+    let start_h = heuristic.estimate(&start_board.state);
+    reached.insert(start_board.state.clone(), (0, Move::None));
+    open.push((Reverse(weighted_f(0, start_h)), 0, start_h, start_board.state.clone()));
+    *open_f.entry(start_h).or_insert(0) += 1;
+
+    while let Some((_, g, h, state)) = open.pop() {
+        remove_count(&mut open_f, g + h);
+
+        let incumbent = best.as_ref().map(|best| best.cost() as i32);
+        if reached[&state].0 < g || incumbent.is_some_and(|cost| g + h >= cost) {
+            continue;
+        }
+
+        if h == 0 && state == goal_board.state {
+            let improvement = Improvement {
+                path: trace_path(&reached, state, size),
+                lower_bound: lower_bound(&open_f, g),
+                nodes_expanded,
+                running_time: start_time.elapsed()
+            };
+
+            let keep_going = on_improvement(&improvement);
+            best = Some(improvement);
+            if !keep_going {
+                break;
+            }
+            continue;
+        }
+
+        if nodes_expanded % LIMIT_CHECK_INTERVAL == 0 {
+            let cancelled = config.cancel.as_ref().is_some_and(|cancel| cancel.is_cancelled());
+            if cancelled || config.limits.reached(nodes_expanded, reached.len(), start_time.elapsed()).is_some() {
+                break;
+            }
+        }
+        nodes_expanded += 1;
+
+        let board = Board::new(size, state);
+        let came_by = reached[&board.state].1;
+
+        for the_move in MOVES.iter() {
+            if *the_move == came_by.inverse() {
+                continue;
+            }
+            let child = match board.apply_move(*the_move) {
+                Some(child) => child,
+                None => continue
+            };
+
+            let child_g = g + 1;
+            let child_h = heuristic.estimate(&child.state);
+            if incumbent.is_some_and(|cost| child_g + child_h >= cost) {
+                continue;
+            }
+            if reached.get(&child.state).is_some_and(|(known_g, _)| *known_g <= child_g) {
+                continue;
+            }
+
+            reached.insert(child.state.clone(), (child_g, *the_move));
+            open.push((Reverse(weighted_f(child_g, child_h)), child_g, child_h, child.state));
+            *open_f.entry(child_g + child_h).or_insert(0) += 1;
+        }
+    }
+
+    Ok(best.map(|mut best| {
+        best.lower_bound = lower_bound(&open_f, best.cost() as i32);
+        best.nodes_expanded = nodes_expanded;
+        best.running_time = start_time.elapsed();
+        best
+    }))
+}
+
+fn remove_count(open_f: &mut BTreeMap<i32, usize>, f: i32) {
+    if let Some(count) = open_f.get_mut(&f) {
+        *count -= 1;
+        if *count == 0 {
+            open_f.remove(&f);
+        }
+    }
+}
+
+// Anything shorter than the incumbent would have to pass through a board still on the open list
+fn lower_bound(open_f: &BTreeMap<i32, usize>, incumbent: i32) -> usize {
+    open_f.keys().next().map_or(incumbent, |f| (*f).min(incumbent)) as usize
+}
+
+// Walk back from the goal using the move that reached each board
+fn trace_path(reached: &HashMap<Vec<u8>, (i32, Move)>, goal_state: Vec<u8>, size: usize) -> Vec<Move> {
+    let mut path = Vec::new();
+    let mut board = Board::new(size, goal_state);
+
+    loop {
+        let came_by = reached[&board.state].1;
+        if came_by == Move::None {
+            break;
+        }
+        path.push(came_by);
+        board = board.apply_move(came_by.inverse()).expect("reached boards are linked by legal moves");
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CancelToken, SearchLimits};
+    use crate::analysis::DistanceTable;
+
+    fn anytime_config() -> SolverConfig {
+        SolverConfig { epsilon: DEFAULT_ANYTIME_WEIGHT, ..SolverConfig::default() }
+    }
+
+    #[test]
+    fn improvements_get_shorter_until_optimal() {
+        let goal = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+
+        for index in (0..table.board_count()).step_by(997) {
+            let board = Board::new(3, crate::ranking::unrank_solvable(index as u128, 3));
+            let mut improvements: Vec<Improvement> = Vec::new();
+            let best = anytime_solver(&board, &goal, &anytime_config(), |improvement| {
+                improvements.push(improvement.clone());
+                true
+            }).unwrap().unwrap();
+
+            assert!(improvements.windows(2).all(|pair| pair[1].cost() < pair[0].cost()), "{:?}", board.state);
+            for improvement in improvements.iter() {
+                assert_eq!(board.apply_moves(&improvement.path).unwrap().state, goal.state);
+                assert!(improvement.lower_bound <= table.distance(&board).unwrap());
+            }
+
+            assert_eq!(best.path, improvements.last().unwrap().path);
+            assert_eq!(best.cost(), table.distance(&board).unwrap(), "{:?}", board.state);
+            assert!(best.is_optimal());
+        }
+    }
+
+    #[test]
+    fn cancelled_tokens_stop_the_search() {
+        let start = Board::new(4, vec![11, 15, 3, 12, 2, 8, 10, 1, 4, 6, 5, 14, 13, 7, 9, 0]);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let config = SolverConfig { cancel: Some(cancel), ..anytime_config() };
+        assert!(anytime_solver(&start, &Board::goal_board(4), &config, |_| true).unwrap().is_none());
+    }
+
+    #[test]
+    fn limits_stop_the_search() {
+        let start = Board::new(4, vec![11, 15, 3, 12, 2, 8, 10, 1, 4, 6, 5, 14, 13, 7, 9, 0]);
+        let config = SolverConfig { limits: SearchLimits { max_nodes: Some(5000), ..SearchLimits::default() }, ..anytime_config() };
+
+        // A first solution comes within the limit, but it's a long way from proven optimal
+        let best = anytime_solver(&start, &Board::goal_board(4), &config, |_| true).unwrap().unwrap();
+        assert!(best.nodes_expanded >= 5000 && best.nodes_expanded <= 5000 + LIMIT_CHECK_INTERVAL);
+        assert!(!best.is_optimal());
+        assert_eq!(start.apply_moves(&best.path).unwrap().state, Board::goal_board(4).state);
+    }
+}
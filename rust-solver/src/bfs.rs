@@ -0,0 +1,349 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{Algorithm, Board, Metrics, Move, SolverConfig, SolverSession};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+pub fn bfs_solver(start_board: &Board, goal_board: &Board) -> Vec<Move> {
+    bfs_solver_with_metrics(start_board, goal_board).path
+}
+
+pub fn bfs_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::Bfs, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
+
+pub fn bidirectional_bfs_solver(start_board: &Board, goal_board: &Board) -> Vec<Move> {
+    bidirectional_bfs_solver_with_metrics(start_board, goal_board).path
+}
+
+pub fn bidirectional_bfs_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::BidirectionalBfs, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Breadth first search out from one end, a layer of boards at a time. Every move costs the same,
+// so this is also uniform cost search
+struct Frontier {
+    size: usize,
+
+    // Boards at `depth` moves still to expand, and the boards found one move further out
+    layer: VecDeque<Vec<u8>>,
+    next_layer: VecDeque<Vec<u8>>,
+    depth: i32,
+
+    // Number of moves from this end to each board reached, and the move that got there
+    reached: HashMap<Vec<u8>, (i32, Move)>,
+
+    // Only used to pick the closest board for a partial path, see Search::best_path
+    heuristic: GoalDistance,
+    closest: (i32, Vec<u8>)
+}
+
+impl Frontier {
+    fn new(from_board: &Board, to_board: &Board) -> Frontier {
+        let mut frontier = Frontier {
+            size: from_board.size,
+            layer: VecDeque::new(),
+            next_layer: VecDeque::new(),
+            depth: 0,
+            reached: HashMap::new(),
+            heuristic: GoalDistance::with_kind(to_board, HeuristicKind::Manhattan),
+            closest: (i32::MAX, Vec::new())
+        };
+
+        frontier.reach(from_board.state.clone(), 0, Move::None);
+        frontier.layer.push_back(from_board.state.clone());
+        frontier
+    }
+
+    fn reach(&mut self, state: Vec<u8>, depth: i32, came_by: Move) {
+        let h = self.heuristic.estimate(&state);
+        if h < self.closest.0 {
+            self.closest = (h, state.clone());
+        }
+        self.reached.insert(state, (depth, came_by));
+    }
+
+    // Next board to expand, moving on to the next layer when this one is done. None once every
+    // board has been expanded
+    fn pop(&mut self) -> Option<Vec<u8>> {
+        if self.layer.is_empty() {
+            if self.next_layer.is_empty() {
+                return None;
+            }
+            std::mem::swap(&mut self.layer, &mut self.next_layer);
+            self.depth += 1;
+        }
+        self.layer.pop_front()
+    }
+
+    // Children of the board not reached before, which are added to the next layer
+    fn expand(&mut self, state: Vec<u8>) -> Vec<Vec<u8>> {
+        let came_by = self.reached[&state].1;
+        let board = Board::new(self.size, state);
+        let mut children = Vec::new();
+
+        for the_move in MOVES.iter() {
+            if *the_move == came_by.inverse() {
+                continue;
+            }
+            if let Some(child) = board.apply_move(*the_move) {
+                if self.reached.contains_key(&child.state) {
+                    continue;
+                }
+                self.reach(child.state.clone(), self.depth + 1, *the_move);
+                self.next_layer.push_back(child.state.clone());
+                children.push(child.state);
+            }
+        }
+        children
+    }
+
+    fn open_count(&self) -> usize {
+        self.layer.len() + self.next_layer.len()
+    }
+
+    // Moves from this frontier's end to the given board
+    fn trace_path(&self, state: &[u8]) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut board = Board::new(self.size, state.to_vec());
+
+        loop {
+            let came_by = self.reached[&board.state].1;
+            if came_by == Move::None {
+                break;
+            }
+            path.push(came_by);
+            board = board.apply_move(came_by.inverse()).expect("reached boards are linked by legal moves");
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+// Plain breadth first search from the start. Optimal, but stores every board within the solution
+// length of the start, so it's only practical up to 3x3 and easy 4x4 boards. Meant as a baseline
+// to check other solvers against
+pub(crate) struct BreadthFirstSearch {
+    goal: Vec<u8>,
+    frontier: Frontier,
+    nodes_expanded: usize
+}
+
+impl BreadthFirstSearch {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board) -> BreadthFirstSearch {
+        BreadthFirstSearch {
+            goal: goal_board.state.clone(),
+            frontier: Frontier::new(start_board, goal_board),
+            nodes_expanded: 0
+        }
+    }
+}
+
+impl Search for BreadthFirstSearch {
+    // Exhausted once every board reachable from the start has been expanded without meeting the goal
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        if self.frontier.reached.contains_key(&self.goal) {
+            return SearchStatus::Solved(self.frontier.trace_path(&self.goal));
+        }
+
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        while self.nodes_expanded < target {
+            let state = match self.frontier.pop() {
+                Some(state) => state,
+                None => return SearchStatus::Exhausted
+            };
+            self.nodes_expanded += 1;
+
+            // Boards are checked as they are reached rather than expanded, which saves a layer
+            if self.frontier.expand(state).contains(&self.goal) {
+                return SearchStatus::Solved(self.frontier.trace_path(&self.goal));
+            }
+        }
+
+        SearchStatus::InProgress
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    fn stored_states(&self) -> usize {
+        self.frontier.reached.len()
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        Some(self.frontier.depth as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.frontier.open_count()]
+    }
+
+    fn best_path(&self) -> Vec<Move> {
+        self.frontier.trace_path(&self.frontier.closest.1)
+    }
+}
+
+// Breadth first search from both ends, expanding whichever side has fewer boards waiting. Stores
+// roughly the square root of what a one sided search does. Any path not found yet has to leave
+// the unexpanded boards of both sides, so it's at least forward depth + backward depth + 1 moves,
+// and the shortest meeting found so far is optimal once it is no longer than that
+pub(crate) struct BidirectionalBfs {
+    forward: Frontier,
+    backward: Frontier,
+
+    // Shortest solution so far, and the board where its two halves meet
+    best: Option<(i32, Vec<u8>)>,
+
+    nodes_expanded: usize
+}
+
+impl BidirectionalBfs {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board) -> BidirectionalBfs {
+        let mut search = BidirectionalBfs {
+            forward: Frontier::new(start_board, goal_board),
+            backward: Frontier::new(goal_board, start_board),
+            best: None,
+            nodes_expanded: 0
+        };
+
+        if start_board.state == goal_board.state {
+            search.best = Some((0, start_board.state.clone()));
+        }
+        search
+    }
+
+    fn lower_bound(&self) -> i32 {
+        self.forward.depth + self.backward.depth + 1
+    }
+
+    fn is_finished(&self) -> bool {
+        match &self.best {
+            Some((cost, _)) => *cost <= self.lower_bound() || self.forward.open_count() == 0 || self.backward.open_count() == 0,
+            None => false
+        }
+    }
+
+    fn solution(&self) -> Vec<Move> {
+        let meeting = &self.best.as_ref().expect("only called once a solution is found").1;
+
+        // The backward half goes from the goal to the meeting board, walk it the other way
+        let mut path = self.forward.trace_path(meeting);
+        let backward = self.backward.trace_path(meeting);
+        path.extend(backward.iter().rev().map(|backward_move| backward_move.inverse()));
+        path
+    }
+}
+
+impl Search for BidirectionalBfs {
+    // Exhausted if either side runs out of boards before the two meet, only possible when the goal
+    // can't be reached
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        loop {
+            if self.is_finished() {
+                return SearchStatus::Solved(self.solution());
+            }
+            if self.nodes_expanded >= target {
+                return SearchStatus::InProgress;
+            }
+
+            let forward = self.forward.open_count() <= self.backward.open_count();
+            let (side, other) = if forward {
+                (&mut self.forward, &self.backward)
+            } else {
+                (&mut self.backward, &self.forward)
+            };
+
+            // With a meeting found an empty side already finished the search (see is_finished)
+            let state = match side.pop() {
+                Some(state) => state,
+                None => return SearchStatus::Exhausted
+            };
+            self.nodes_expanded += 1;
+
+            for child in side.expand(state) {
+                if let Some((other_depth, _)) = other.reached.get(&child) {
+                    let cost = side.depth + 1 + other_depth;
+                    if self.best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                        self.best = Some((cost, child));
+                    }
+                }
+            }
+        }
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    fn stored_states(&self) -> usize {
+        self.forward.reached.len() + self.backward.reached.len()
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        let bound = self.lower_bound();
+        Some(self.best.as_ref().map_or(bound, |(cost, _)| bound.min(*cost)) as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.forward.open_count(), self.backward.open_count()]
+    }
+
+    // The best solution so far if there is one, otherwise the forward side's closest approach
+    fn best_path(&self) -> Vec<Move> {
+        match self.best {
+            Some(_) => self.solution(),
+            None => self.forward.trace_path(&self.forward.closest.1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::DistanceTable;
+
+    // Each optimal solver against the exact distances on a fixed sample of the 3x3 boards. Plain
+    // breadth first search reaches most of the state space for every board, so it gets a sparser
+    // sample to keep debug builds quick
+    #[test]
+    fn optimal_solvers_match_the_distance_table() {
+        let table = DistanceTable::build(&Board::goal_board(3)).unwrap();
+        let samples = [
+            (Algorithm::Bfs, 9973),
+            (Algorithm::BidirectionalBfs, 997),
+            (Algorithm::IdaStar, 499),
+            (Algorithm::FrontierAStar, 499),
+            (Algorithm::Bidirectional, 499),
+            (Algorithm::WeightedAStar, 499)
+        ];
+
+        for (algorithm, stride) in samples.iter() {
+            let config = SolverConfig { algorithm: *algorithm, epsilon: 1.0, ..SolverConfig::default() };
+            let checked = table.crosscheck(&config, *stride).unwrap_or_else(|err| panic!("{}", err));
+            assert_eq!(checked, table.board_count().div_ceil(*stride));
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_exhaust_the_search() {
+        let start = Board::new(2, vec![2, 1, 3, 0]);
+        let goal = Board::goal_board(2);
+
+        let mut bfs = BreadthFirstSearch::new(&start, &goal);
+        assert!(matches!(bfs.step(usize::MAX), SearchStatus::Exhausted));
+        assert!(matches!(bfs.step(usize::MAX), SearchStatus::Exhausted));
+
+        let mut bidirectional = BidirectionalBfs::new(&start, &goal);
+        assert!(matches!(bidirectional.step(usize::MAX), SearchStatus::Exhausted));
+        assert!(matches!(bidirectional.step(usize::MAX), SearchStatus::Exhausted));
+    }
+}
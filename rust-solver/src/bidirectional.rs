@@ -0,0 +1,324 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use super::{Algorithm, Board, Metrics, Move, SolverConfig, SolverSession};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+pub fn bidirectional_solver(start_board: &Board, goal_board: &Board) -> Vec<Move> {
+    bidirectional_solver_with_metrics(start_board, goal_board).path
+}
+
+pub fn bidirectional_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::Bidirectional, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// One half of the bidirectional search, an A* from one end towards the other
+struct Direction {
+    // Estimates the distance to the opposite end of the search
+    heuristic: GoalDistance,
+
+    // Epsilon in thousandths, so the open list can use integer keys
+    weight: i64,
+
+    // Ordered by g + epsilon * h, then deepest first. Entries hold the unweighted f as well, and
+    // go stale when a cheaper way to the board is found, they are skipped when popped
+    open: BinaryHeap<(Reverse<i64>, i32, i32, Vec<u8>)>,
+
+    // How many open list entries there are for each unweighted f and each g, for the lower bound
+    open_f: BTreeMap<i32, usize>,
+    open_g: BTreeMap<i32, usize>,
+
+    // Cheapest known cost from this direction's end to each board reached, and the move that
+    // got there
+    reached: HashMap<Vec<u8>, (i32, Move)>,
+
+    // The reached board with the smallest estimate, and that estimate
+    closest: (i32, Vec<u8>)
+}
+
+impl Direction {
+    fn new(from_board: &Board, to_board: &Board, kind: HeuristicKind, weight: i64) -> Direction {
+        let mut direction = Direction {
+            heuristic: GoalDistance::with_kind(to_board, kind),
+            weight,
+            open: BinaryHeap::new(),
+            open_f: BTreeMap::new(),
+            open_g: BTreeMap::new(),
+            reached: HashMap::new(),
+            closest: (i32::MAX, Vec::new())
+        };
+
+        direction.push(from_board.state.clone(), 0, Move::None);
+        direction
+    }
+
+    fn estimate(&self, state: &[u8]) -> i32 {
+        self.heuristic.estimate(state)
+    }
+
+    fn push(&mut self, state: Vec<u8>, g: i32, came_by: Move) {
+        let h = self.estimate(&state);
+        if h < self.closest.0 {
+            self.closest = (h, state.clone());
+        }
+
+        self.reached.insert(state.clone(), (g, came_by));
+        self.open.push((Reverse(1000 * g as i64 + self.weight * h as i64), g, g + h, state));
+        *self.open_f.entry(g + h).or_insert(0) += 1;
+        *self.open_g.entry(g).or_insert(0) += 1;
+    }
+
+    // Best board on the open list with its cost, skipping stale entries
+    fn pop(&mut self) -> Option<(i32, Vec<u8>)> {
+        while let Some((_, g, f, state)) = self.open.pop() {
+            remove_count(&mut self.open_f, f);
+            remove_count(&mut self.open_g, g);
+
+            if self.reached[&state].0 == g {
+                return Some((g, state));
+            }
+        }
+        None
+    }
+
+    // Smallest unweighted f and smallest g on the open list, None once it is empty
+    fn open_minimums(&self) -> Option<(i32, i32)> {
+        Some((*self.open_f.keys().next()?, *self.open_g.keys().next()?))
+    }
+
+    // Moves from this direction's end to the given board
+    fn trace_path(&self, state: &[u8], size: usize) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut board = Board::new(size, state.to_vec());
+
+        loop {
+            let came_by = self.reached[&board.state].1;
+            if came_by == Move::None {
+                break;
+            }
+            path.push(came_by);
+            board = board.apply_move(came_by.inverse()).expect("reached boards are linked by legal moves");
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+fn remove_count(counts: &mut BTreeMap<i32, usize>, key: i32) {
+    if let Some(count) = counts.get_mut(&key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&key);
+        }
+    }
+}
+
+// Front-to-end bidirectional A*. One A* runs forward from the start using an estimate of the
+// distance to the goal, the other runs backward from the goal using an estimate of the distance
+// to the start, and g is always the number of moves from that direction's end. Each step expands
+// a board from whichever side has the smaller open list.
+//
+// Every time a board is reached from one side that the other side has already reached, the two
+// halves give a solution. The search doesn't stop at the first one: with C the optimal length,
+// an undiscovered shorter path must still pass through a board on each open list, so
+//   C >= max(min f forward, min f backward, min g forward + min g backward + 1)
+// and the search stops once the best solution is within epsilon of that bound. With epsilon 1
+// the result is optimal, larger values order each side by g + epsilon * h to finish sooner and
+// return a path at most epsilon times the optimal length.
+//
+// Boards are re-expanded when a cheaper way to them is found, which the bound relies on
+pub(crate) struct BidirectionalSearch {
+    size: usize,
+    forward: Direction,
+    backward: Direction,
+    weight: i64,
+
+    // Shortest solution so far, and the board where its two halves meet
+    best: Option<(i32, Vec<u8>)>,
+
+    nodes_expanded: usize
+}
+
+impl BidirectionalSearch {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board, kind: HeuristicKind, epsilon: f64) -> BidirectionalSearch {
+        let weight = (epsilon.max(1.0) * 1000.0).round() as i64;
+
+        let mut search = BidirectionalSearch {
+            size: start_board.size,
+            forward: Direction::new(start_board, goal_board, kind, weight),
+            backward: Direction::new(goal_board, start_board, kind, weight),
+            weight,
+            best: None,
+            nodes_expanded: 0
+        };
+
+        if start_board.state == goal_board.state {
+            search.best = Some((0, start_board.state.clone()));
+        }
+        search
+    }
+
+    // Whether the best solution so far is proven to be within epsilon of optimal
+    fn is_finished(&self) -> bool {
+        let cost = match &self.best {
+            Some((cost, _)) => *cost,
+            None => return false
+        };
+
+        // Once either side runs out of boards every path has been seen
+        match self.lower_bound() {
+            Some(lower_bound) => 1000 * cost as i64 <= self.weight * lower_bound as i64,
+            None => true
+        }
+    }
+
+    // No path shorter than this is left undiscovered, None once either open list is empty
+    fn lower_bound(&self) -> Option<i32> {
+        let (forward_f, forward_g) = self.forward.open_minimums()?;
+        let (backward_f, backward_g) = self.backward.open_minimums()?;
+
+        Some(forward_f.max(backward_f).max(forward_g + backward_g + 1))
+    }
+
+    fn solution(&self) -> Vec<Move> {
+        let meeting = &self.best.as_ref().expect("only called once a solution is found").1;
+
+        // The backward half goes from the goal to the meeting board, walk it the other way
+        let mut path = self.forward.trace_path(meeting, self.size);
+        let backward = self.backward.trace_path(meeting, self.size);
+        path.extend(backward.iter().rev().map(|backward_move| backward_move.inverse()));
+        path
+    }
+}
+
+impl Search for BidirectionalSearch {
+    // Exhausted if either side runs out of boards before the two meet, only possible when the goal
+    // can't be reached
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        loop {
+            if self.is_finished() {
+                return SearchStatus::Solved(self.solution());
+            }
+            if self.nodes_expanded >= target {
+                return SearchStatus::InProgress;
+            }
+
+            let forward = self.forward.open.len() <= self.backward.open.len();
+            let (side, other) = if forward {
+                (&mut self.forward, &self.backward)
+            } else {
+                (&mut self.backward, &self.forward)
+            };
+
+            // A side that runs out of boards has seen every board it can reach. With a meeting
+            // found that finishes the search (see is_finished), without one the ends can't meet
+            let (g, state) = match side.pop() {
+                Some(entry) => entry,
+                None if self.best.is_some() => continue,
+                None => return SearchStatus::Exhausted
+            };
+            self.nodes_expanded += 1;
+
+            let best_cost = self.best.as_ref().map(|(cost, _)| *cost);
+            let came_by = side.reached[&state].1;
+            let board = Board::new(self.size, state);
+
+            for the_move in MOVES.iter() {
+                if *the_move == came_by.inverse() {
+                    continue;
+                }
+                let child = match board.apply_move(*the_move) {
+                    Some(child) => child,
+                    None => continue
+                };
+
+                let child_g = g + 1;
+                if side.reached.get(&child.state).is_some_and(|(known_g, _)| *known_g <= child_g) {
+                    continue;
+                }
+
+                // Nothing through this board can beat the best solution so far
+                if best_cost.is_some_and(|cost| child_g + side.estimate(&child.state) >= cost) {
+                    continue;
+                }
+
+                if let Some((other_g, _)) = other.reached.get(&child.state) {
+                    let cost = child_g + other_g;
+                    if self.best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                        self.best = Some((cost, child.state.clone()));
+                    }
+                }
+
+                side.push(child.state, child_g, *the_move);
+            }
+        }
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    fn stored_states(&self) -> usize {
+        self.forward.reached.len() + self.backward.reached.len()
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        self.lower_bound().map(|bound| bound as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.forward.open.len(), self.backward.open.len()]
+    }
+
+    // The best solution so far if there is one, otherwise the forward half's closest approach
+    fn best_path(&self) -> Vec<Move> {
+        match self.best {
+            Some(_) => self.solution(),
+            None => self.forward.trace_path(&self.forward.closest.1, self.size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve;
+    use crate::analysis::DistanceTable;
+
+    #[test]
+    fn epsilon_1_paths_are_optimal_and_larger_epsilons_stay_within_bound() {
+        let goal = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+
+        for epsilon in [1.0, 2.5].iter() {
+            let config = SolverConfig { algorithm: Algorithm::Bidirectional, epsilon: *epsilon, ..SolverConfig::default() };
+            for index in (0..table.board_count()).step_by(499) {
+                let board = Board::new(3, crate::ranking::unrank_solvable(index as u128, 3));
+                let optimal = table.distance(&board).unwrap();
+
+                let path = solve(&board, &goal, &config).unwrap().path;
+                assert_eq!(board.apply_moves(&path).unwrap().state, goal.state);
+                if *epsilon == 1.0 {
+                    assert_eq!(path.len(), optimal, "{:?}", board.state);
+                } else {
+                    assert!(path.len() as f64 <= epsilon * optimal as f64, "{:?}: {} moves at epsilon {}, optimal {}", board.state, path.len(), epsilon, optimal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_exhaust_the_search() {
+        let start = Board::new(2, vec![2, 1, 3, 0]);
+        let mut search = BidirectionalSearch::new(&start, &Board::goal_board(2), HeuristicKind::LinearConflict, 1.0);
+        assert!(matches!(search.step(usize::MAX), SearchStatus::Exhausted));
+        assert!(matches!(search.step(usize::MAX), SearchStatus::Exhausted));
+    }
+}
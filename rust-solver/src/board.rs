@@ -1,6 +1,16 @@
 use std::hash::{Hash, Hasher};
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use super::ranking;
+use super::heuristics::HeuristicKind;
+use super::ida_star::IdaStar;
+use super::search::{Search, SearchStatus};
+
+// Smallest and largest sizes a board can be, see Board::validate
+pub const MIN_BOARD_SIZE: usize = 2;
+pub const MAX_BOARD_SIZE: usize = 16;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Move {
@@ -11,22 +21,100 @@ pub enum Move {
     None
 }
 
+// Most nodes generate_board_with_difficulty spends measuring boards, across all its attempts.
+// Takes a few seconds in a release build
+const GENERATION_NODE_BUDGET: usize = 50_000_000;
+
+// Preset ranges of optimal solution length for generate_board_with_difficulty
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard
+}
+
+impl Difficulty {
+    // Inclusive (min, max) optimal solution length for the given board size. The 2x2 and 3x3
+    // ranges are scaled to the longest any board of that size needs (6 and 31 moves)
+    pub fn move_range(self, size: usize) -> (usize, usize) {
+        match (size, self) {
+            (2, Difficulty::Easy)   => (1, 2),
+            (2, Difficulty::Medium) => (3, 4),
+            (2, Difficulty::Hard)   => (5, 6),
+            (3, Difficulty::Easy)   => (4, 10),
+            (3, Difficulty::Medium) => (12, 20),
+            (3, Difficulty::Hard)   => (22, 31),
+            (_, Difficulty::Easy)   => (10, 20),
+            (_, Difficulty::Medium) => (25, 35),
+            (_, Difficulty::Hard)   => (40, 50)
+        }
+    }
+}
+
+impl Move {
+    // The move that undoes this one
+    pub fn inverse(self) -> Move {
+        match self {
+            Move::Up    => Move::Down,
+            Move::Down  => Move::Up,
+            Move::Left  => Move::Right,
+            Move::Right => Move::Left,
+            Move::None  => Move::None
+        }
+    }
+}
+
+// One move in the slide-tile metric: every tile between the blank and a tile in the same row or
+// column slides over by one, which counts as a single move. Written as the direction the blank
+// travels and how many squares it goes, so a slide of 1 is the same as a single Move
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Slide {
+    pub direction: Move,
+    pub tiles: usize
+}
+
+impl Slide {
+    pub fn inverse(self) -> Slide {
+        Slide { direction: self.direction.inverse(), tiles: self.tiles }
+    }
+
+    // The same slide as single tile moves
+    pub fn to_moves(self) -> Vec<Move> {
+        vec![self.direction; self.tiles]
+    }
+
+    pub fn slides_to_moves(slides: &[Slide]) -> Vec<Move> {
+        slides.iter().flat_map(|slide| slide.to_moves()).collect()
+    }
+
+    // Groups runs of the same single tile move into slides. Any path can be counted this way,
+    // ex. the number of slides is a solution's length in the slide-tile metric
+    pub fn moves_to_slides(moves: &[Move]) -> Vec<Slide> {
+        let mut slides: Vec<Slide> = Vec::new();
+        for the_move in moves {
+            match slides.last_mut() {
+                Some(slide) if slide.direction == *the_move => slide.tiles += 1,
+                _ if *the_move == Move::None => (),
+                _ => slides.push(Slide { direction: *the_move, tiles: 1 })
+            }
+        }
+        slides
+    }
+}
+
 pub struct Board {
     // The size of row/column for the board
     pub size: usize,
 
     // The current layout of the board
-    //state: [u8; 9],
-    pub state: [u8; 16],
+    // Tiles in row-major order, 0 is the blank square. Always size*size entries
+    pub state: Vec<u8>,
 
     // The parent board this board came from
     pub m_list: Vec<Move>,
 
     // Count of how many moves from start board
-    pub cost: i32,
-
-    // Heuristics value for this board
-    pub score: i32
+    pub cost: i32
 }
 
 impl Clone for Board {
@@ -35,8 +123,7 @@ impl Clone for Board {
             size: self.size,
             state: self.state.clone(),
             m_list: self.m_list.clone(),
-            cost:  self.cost,
-            score: self.score
+            cost:  self.cost
         }
     }
 }
@@ -57,63 +144,76 @@ impl Eq for Board {}
 
 // Public functions
 impl Board {
-    pub fn expand(&self, heuristics: &super::Heuristic) -> Vec<Board> {
-        let mut moves = Vec::new();
-
-        let move_up    = Board::move_up(&self);
-        if let Some(mut board) = move_up {
-            board.score = heuristics.calculate_move_cost(&board);
-            moves.push(board);
-        }
-
-        let move_down  = Board::move_down(&self);
-        if let Some(mut board) = move_down {
-            board.score = heuristics.calculate_move_cost(&board);
-            moves.push(board);
+    pub fn new(size: usize, state: Vec<u8>) -> Board {
+        Board {
+            size,
+            state,
+            m_list: Vec::new(),
+            cost: 0
         }
+    }
 
-        let move_left  = Board::move_left(&self);
-        if let Some(mut board) = move_left {
-            board.score = heuristics.calculate_move_cost(&board);
-            moves.push(board);
-        }
+    // The solved layout, tiles in order with the blank square in the bottom right
+    pub fn goal_board(size: usize) -> Board {
+        let mut state : Vec<u8> = (1..size*size).map(|x| x as u8).collect();
+        state.push(0);
+        Board::new(size, state)
+    }
 
-        let move_right = Board::move_right(&self);
-        if let Some(mut board) = move_right {
-            board.score = heuristics.calculate_move_cost(&board);
-            moves.push(board);
+    // Move the blank square in the given direction, None if it would leave the board
+    pub fn apply_move(&self, the_move: Move) -> Option<Board> {
+        match the_move {
+            Move::Up    => Board::move_up(self),
+            Move::Down  => Board::move_down(self),
+            Move::Left  => Board::move_left(self),
+            Move::Right => Board::move_right(self),
+            Move::None  => Some(self.clone())
         }
-
-        moves
     }
 
-    pub fn is_solvable(&self) -> bool {
-        let mut inversions = 0;
-        let mut zero_row = 0;
-        let row_size = self.size;
+    // Slide a line of tiles into the blank, None if it would leave the board. Counts as one move
+    pub fn apply_slide(&self, slide: Slide) -> Option<Board> {
+        let mut board = self.apply_moves(&slide.to_moves())?;
+        board.cost = self.cost + 1;
+        Some(board)
+    }
 
-        for index in 0..self.state.len() {
+    pub fn apply_moves(&self, moves: &[Move]) -> Option<Board> {
+        let mut board = self.clone();
+        for the_move in moves {
+            board = board.apply_move(*the_move)?;
+        }
+        Some(board)
+    }
 
-            let current = self.state[index];
-            if current == 0 {
-                zero_row = index / row_size;
-                continue;
-            }
+    // Checks the layout is a permutation of 0..size*size
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size < MIN_BOARD_SIZE || self.size > MAX_BOARD_SIZE {
+            return Err(format!("board size must be between {} and {}, got {}", MIN_BOARD_SIZE, MAX_BOARD_SIZE, self.size));
+        }
+        if self.state.len() != self.size * self.size {
+            return Err(format!("a {}x{} board needs {} tiles, got {}", self.size, self.size, self.size * self.size, self.state.len()));
+        }
 
-            for nindex in index+1..self.state.len() {
-                if self.state[nindex] != 0 && current > self.state[nindex] {
-                    inversions += 1;
-                }
+        let mut seen = vec![false; self.state.len()];
+        for tile in &self.state {
+            let tile = *tile as usize;
+            if tile >= seen.len() || seen[tile] {
+                return Err(format!("tiles must be each of 0 to {} exactly once", seen.len() - 1));
             }
+            seen[tile] = true;
         }
+        Ok(())
+    }
 
-        // For odd size boards (ex. 3x3) number of inversions must be even
-        if self.state.len() % 2 != 0 {
-            return inversions % 2 == 0;
-        }
+    // Whether the goal board can be reached from this one. Every layout can reach exactly one of
+    // the standard goal or the standard goal with two tiles swapped, so it's enough to compare
+    pub fn can_reach(&self, goal_board: &Board) -> bool {
+        self.is_solvable() == goal_board.is_solvable()
+    }
 
-        // For even size boards (ex. 4x4) number of inversions + row of blank square must be odd
-        (inversions + zero_row) % 2 != 0
+    pub fn is_solvable(&self) -> bool {
+        Board::is_solvable_arr(&self.state, self.size)
     }
 
     pub fn get_index_of(&self, input: &u8) -> usize {
@@ -129,23 +229,110 @@ impl Board {
     }
 
     pub fn generate_board(size: usize) -> Board {
-        let mut solvable = false;
+        Board::generate_board_with_rng(size, &mut thread_rng())
+    }
+
+    // The same seed always gives the same board, on every platform (including wasm). ChaCha8 is
+    // used explicitly rather than StdRng since StdRng's algorithm may change between rand versions
+    pub fn generate_board_seeded(size: usize, seed: u64) -> Board {
+        Board::generate_board_with_rng(size, &mut ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    // Every solvable board is equally likely. Rather than shuffling until the result happens to be
+    // solvable, draw a uniform rank over the solvable boards and unrank it directly
+    pub fn generate_board_with_rng<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Board {
+        if let Some(count) = ranking::solvable_state_count(size) {
+            let rank = rng.gen_range(0..count);
+            return Board::new(size, ranking::unrank_solvable(rank, size));
+        }
+
+        // Too many boards to rank (6x6 and up). Shuffle once and if that landed on an unsolvable
+        // board swap the first two tiles. For a given blank position that swap pairs every
+        // unsolvable board with exactly one solvable board, so the result is still uniform
         let mut values : Vec<u8> = (0..size*size).map(|x| x as u8).collect();
+        values.shuffle(rng);
 
-        while !solvable {
-            values.shuffle(&mut thread_rng());
+        if !Board::is_solvable_arr(&values, size) {
+            let tiles: Vec<usize> = (0..values.len()).filter(|index| values[*index] != 0).take(2).collect();
+            values.swap(tiles[0], tiles[1]);
+        }
+
+        Board::new(size, values)
+    }
 
-            let state = Board::vec_to_array(&values);
-            solvable = Board::is_solvable_arr(state, size);
+    // A board whose optimal solution is between min_moves and max_moves long (inclusive), along
+    // with that optimal length. Boards are made by walking randomly away from the goal and then
+    // measuring the true distance with IDA*, adjusting the walk length until the distance lands
+    // in range. None if nothing was found, ex. the range is longer than any board of that size
+    // needs, or measuring took more than GENERATION_NODE_BUDGET nodes all told. Without that
+    // budget long ranges on 5x5 and larger boards would never finish
+    pub fn generate_board_with_difficulty<R: Rng + ?Sized>(size: usize, min_moves: usize, max_moves: usize, rng: &mut R) -> Option<(Board, usize)> {
+        const MAX_ATTEMPTS: usize = 100;
+
+        let goal = Board::goal_board(size);
+        let mut walk_length = max_moves;
+        let mut nodes_left  = GENERATION_NODE_BUDGET;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let board = Board::new(size, goal.random_walk(walk_length, rng).state);
+
+            let mut search = IdaStar::new(&board, &goal, HeuristicKind::LinearConflict);
+            let optimal = match search.step(nodes_left) {
+                SearchStatus::Solved(path) => path.len(),
+                _ => return None
+            };
+            nodes_left -= search.nodes_expanded().min(nodes_left);
+
+            if optimal >= min_moves && optimal <= max_moves {
+                return Some((board, optimal));
+            }
+
+            // Walks double back on themselves, so the optimal distance is usually shorter than
+            // the walk. Lengthen (or shorten) the walk by however far we missed
+            if optimal < min_moves {
+                walk_length += min_moves - optimal;
+            }
+            else {
+                walk_length -= optimal - max_moves;
+            }
         }
 
-        Board {
-            size,
-            state: Board::vec_to_array(&values),
-            m_list: Vec::new(),
-            cost : 0,
-            score: 0
+        None
+    }
+
+    pub fn generate_board_with_difficulty_seeded(size: usize, min_moves: usize, max_moves: usize, seed: u64) -> Option<(Board, usize)> {
+        Board::generate_board_with_difficulty(size, min_moves, max_moves, &mut ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    // Make the given number of random moves, never immediately undoing the previous one
+    pub fn random_walk<R: Rng + ?Sized>(&self, moves: usize, rng: &mut R) -> Board {
+        let directions = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+        let mut board = self.clone();
+        let mut last  = Move::None;
+        let mut steps = 0;
+
+        while steps < moves {
+            let the_move = directions[rng.gen_range(0..directions.len())];
+            if the_move == last.inverse() {
+                continue;
+            }
+            if let Some(next) = board.apply_move(the_move) {
+                board = next;
+                last  = the_move;
+                steps += 1;
+            }
         }
+        board
+    }
+
+    // Lexicographic rank of the layout, a perfect hash for boards up to 5x5
+    pub fn rank(&self) -> u128 {
+        ranking::rank(&self.state)
+    }
+
+    pub fn unrank(size: usize, rank: u128) -> Board {
+        Board::new(size, ranking::unrank(rank, size*size))
     }
 
     pub fn print(&self) {
@@ -175,16 +362,13 @@ impl Board {
         let index = parent_board.get_index_of(&0);
 
         // Cant move left from first column
-        if index % parent_board.size == 0 {
+        if index.is_multiple_of(parent_board.size) {
             return None
         }
 
-        let mut new_state = parent_board.state.clone();
-        new_state[index]   = new_state[index-1]; 
-        new_state[index-1] = 0;
-
         let mut board = parent_board.clone();
-        board.state = new_state;
+        board.state[index] = board.state[index-1];
+        board.state[index-1] = 0;
         board.cost += 1;
         board.m_list.push(Move::Left);
         Some(board)
@@ -198,12 +382,9 @@ impl Board {
             return None
         }
 
-        let mut new_state = parent_board.state.clone();
-        new_state[index]   = new_state[index+1]; 
-        new_state[index+1] = 0;
-
         let mut board = parent_board.clone();
-        board.state = new_state;
+        board.state[index] = board.state[index+1];
+        board.state[index+1] = 0;
         board.cost += 1;
         board.m_list.push(Move::Right);
         Some(board)
@@ -217,12 +398,9 @@ impl Board {
             return None
         }
 
-        let mut new_state = parent_board.state.clone();
-        new_state[index]   = new_state[index-parent_board.size]; 
-        new_state[index-parent_board.size] = 0;
-
         let mut board = parent_board.clone();
-        board.state = new_state;
+        board.state[index] = board.state[index-parent_board.size];
+        board.state[index-parent_board.size] = 0;
         board.cost += 1;
         board.m_list.push(Move::Up);
         Some(board)
@@ -236,18 +414,15 @@ impl Board {
             return None
         }
 
-        let mut new_state = parent_board.state.clone();
-        new_state[index]   = new_state[index+parent_board.size]; 
-        new_state[index+parent_board.size] = 0;
-
         let mut board = parent_board.clone();
-        board.state = new_state;
+        board.state[index] = board.state[index+parent_board.size];
+        board.state[index+parent_board.size] = 0;
         board.cost += 1;
         board.m_list.push(Move::Down);
         Some(board)
     }
 
-    fn is_solvable_arr(state: [u8;16], row_size: usize) -> bool {
+    fn is_solvable_arr(state: &[u8], row_size: usize) -> bool {
         let mut inversions = 0;
         let mut zero_row = 0;
 
@@ -259,15 +434,15 @@ impl Board {
                 continue;
             }
 
-            for nindex in index+1..row_size*row_size {
-                if state[nindex] != 0 && current > state[nindex] {
+            for next in &state[index+1..row_size*row_size] {
+                if *next != 0 && current > *next {
                     inversions += 1;
                 }
             }
         }
 
         // For odd size boards (ex. 3x3) number of inversions must be even
-        if row_size % 2 != 0 {
+        if !row_size.is_multiple_of(2) {
             return inversions % 2 == 0;
         }
 
@@ -275,15 +450,44 @@ impl Board {
         (inversions + zero_row) % 2 != 0
     }
 
-    fn vec_to_array(vector : &Vec<u8>) -> [u8;16] {
-        let mut array : [u8;16] = [0;16];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::DistanceTable;
 
-        let mut ii : usize = 0;
-        for entry in vector {
-            array[ii] = *entry;
-            ii += 1;
+    #[test]
+    fn difficulty_boards_land_in_range() {
+        let table = DistanceTable::build(&Board::goal_board(3)).unwrap();
+
+        for (seed, difficulty) in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard].iter().enumerate() {
+            let (min_moves, max_moves) = difficulty.move_range(3);
+            let (board, optimal) = Board::generate_board_with_difficulty_seeded(3, min_moves, max_moves, seed as u64).unwrap();
+
+            assert!(optimal >= min_moves && optimal <= max_moves);
+            assert_eq!(table.distance(&board), Some(optimal));
         }
-        array
+
+        let (min_moves, max_moves) = Difficulty::Medium.move_range(4);
+        let (_, optimal) = Board::generate_board_with_difficulty_seeded(4, min_moves, max_moves, 7).unwrap();
+        assert!(optimal >= min_moves && optimal <= max_moves);
     }
 
+    // Seeds are shared (ex. 'generate --seed' from the command line and generate_seeded in the
+    // browser), so a change to the generator or its rng must not change the boards they give
+    #[test]
+    fn seeded_boards_never_change() {
+        assert_eq!(Board::generate_board_seeded(3, 42).state, vec![6, 2, 3, 1, 7, 0, 8, 5, 4]);
+        assert_eq!(Board::generate_board_seeded(4, 42).state, vec![4, 1, 15, 2, 7, 14, 8, 3, 13, 5, 10, 11, 12, 6, 9, 0]);
+        assert_eq!(Board::generate_board_seeded(6, 42).state, vec![
+            23, 20, 21, 24, 29, 31, 0, 2, 13, 1, 6, 3, 9, 15, 33, 12, 17, 16,
+            7, 10, 34, 18, 14, 30, 27, 26, 28, 4, 35, 22, 19, 11, 25, 32, 5, 8
+        ]);
+    }
+
+    #[test]
+    fn impossible_difficulties_give_up() {
+        assert!(Board::generate_board_with_difficulty_seeded(3, 32, 40, 1).is_none());
+    }
 }
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Shared flag for stopping a solve from elsewhere, ex. a Ctrl-C handler or another thread. Clones
+// share the flag, so keep one and put a clone in SolverConfig::cancel. The session checks it
+// between slices of the search, stopping within a few thousand nodes of it being set
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
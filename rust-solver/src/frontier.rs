@@ -0,0 +1,369 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use super::{Board, Move, SolveError};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::ida_star::IdaStar;
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Boards are packed into a u128, 4 bits a tile up to 4x4 and 5 bits up to 5x5
+pub const MAX_PACKED_SIZE: usize = 5;
+
+// Segments estimated shorter than this are solved with IDA* rather than split again
+const SPLIT_THRESHOLD: i32 = 20;
+
+// Packed key that no board packs to, every board has a non-zero tile somewhere
+const NO_RELAY: u128 = 0;
+
+fn bits_per_tile(cells: usize) -> u32 {
+    if cells <= 16 { 4 } else { 5 }
+}
+
+fn pack(state: &[u8]) -> u128 {
+    let bits = bits_per_tile(state.len());
+    state.iter().rev().fold(0, |packed, tile| (packed << bits) | *tile as u128)
+}
+
+fn unpack(packed: u128, cells: usize) -> Vec<u8> {
+    let bits = bits_per_tile(cells);
+    let mask = (1u128 << bits) - 1;
+    (0..cells).map(|index| ((packed >> (index as u32 * bits)) & mask) as u8).collect()
+}
+
+fn move_bit(the_move: Move) -> u8 {
+    match the_move {
+        Move::Up    => 1,
+        Move::Down  => 2,
+        Move::Left  => 4,
+        Move::Right => 8,
+        Move::None  => 0
+    }
+}
+
+// A board on the frontier. There is no closed list, so instead each board remembers which of
+// its moves lead to boards that have already been expanded (or are on the frontier) and never
+// makes them. A few bytes plus the relay board on top of the key
+struct FrontierNode {
+    g: i32,
+    used_moves: u8,
+
+    // The board this one's path passed through at the relay depth, NO_RELAY until it gets there
+    relay: u128
+}
+
+// One A* pass of frontier search (Korf). Finds the optimal cost from start to goal, and the board
+// an optimal path passes through at the relay depth, while storing only the frontier
+struct FrontierPass {
+    size: usize,
+    goal: u128,
+    relay_depth: i32,
+    heuristic: GoalDistance,
+
+    // Ordered by f, then deepest first. Entries go stale when a board is reached more cheaply or
+    // has been expanded, they are skipped when popped
+    open: BinaryHeap<(Reverse<i32>, i32, u128)>,
+    frontier: HashMap<u128, FrontierNode>,
+
+    nodes_expanded: usize
+}
+
+enum PassStatus {
+    InProgress,
+
+    // The goal was reached optimally, through this board at the relay depth. Segments are only
+    // split when the goal is estimated at least twice the relay depth away, so it's always set
+    Found(u128),
+
+    // The open list ran out without reaching the goal, so it can't be reached from the start
+    Exhausted
+}
+
+impl FrontierPass {
+    fn new(start: &Board, goal: &Board, kind: HeuristicKind, relay_depth: i32) -> FrontierPass {
+        let mut pass = FrontierPass {
+            size: start.size,
+            goal: pack(&goal.state),
+            relay_depth,
+            heuristic: GoalDistance::with_kind(goal, kind),
+            open: BinaryHeap::new(),
+            frontier: HashMap::new(),
+            nodes_expanded: 0
+        };
+
+        let key = pack(&start.state);
+        pass.open.push((Reverse(pass.heuristic.estimate(&start.state)), 0, key));
+        pass.frontier.insert(key, FrontierNode { g: 0, used_moves: 0, relay: NO_RELAY });
+        pass
+    }
+
+    fn step(&mut self, budget: usize) -> PassStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        while self.nodes_expanded < target {
+            let (_, g, key) = match self.open.pop() {
+                Some(entry) => entry,
+                None => return PassStatus::Exhausted
+            };
+            if self.frontier.get(&key).is_none_or(|node| node.g != g) {
+                continue;
+            }
+            if key == self.goal {
+                return PassStatus::Found(self.frontier[&key].relay);
+            }
+
+            let node = self.frontier.remove(&key).expect("checked above");
+            self.nodes_expanded += 1;
+
+            let state = unpack(key, self.size * self.size);
+            let board = Board::new(self.size, state);
+
+            for the_move in MOVES.iter() {
+                if node.used_moves & move_bit(*the_move) != 0 {
+                    continue;
+                }
+                let child = match board.apply_move(*the_move) {
+                    Some(child) => child,
+                    None => continue
+                };
+
+                let child_key = pack(&child.state);
+                let child_g = g + 1;
+                let relay = if child_g == self.relay_depth { child_key } else { node.relay };
+                let back = move_bit(the_move.inverse());
+
+                match self.frontier.get_mut(&child_key) {
+                    Some(existing) => {
+                        existing.used_moves |= back;
+                        if child_g >= existing.g {
+                            continue;
+                        }
+                        existing.g = child_g;
+                        existing.relay = relay;
+                    },
+                    None => {
+                        self.frontier.insert(child_key, FrontierNode { g: child_g, used_moves: back, relay });
+                    }
+                }
+                self.open.push((Reverse(child_g + self.heuristic.estimate(&child.state)), child_g, child_key));
+            }
+        }
+
+        PassStatus::InProgress
+    }
+}
+
+// Where a segment of the path is being solved
+enum Segment {
+    Split(Board, Board, FrontierPass),
+    Direct(IdaStar)
+}
+
+// Optimal A* that keeps only the frontier (the open list), dropping expanded boards by
+// remembering which moves of each frontier board lead back into the expanded region. Boards are
+// packed into 16 bytes, so this needs a fraction of the memory of WeightedAStar at epsilon 1.
+//
+// Without a closed list there are no parent links to trace the path back with, so it's found by
+// divide and conquer: each pass records which board an optimal path goes through halfway (by
+// the estimate), then both halves are solved the same way. Short halves are handed to IDA*.
+// Relies on the heuristic being consistent, which Manhattan distance and linear conflicts are
+pub(crate) struct FrontierAStar {
+    kind: HeuristicKind,
+
+    // Pieces of the path still to solve, in order, and the one being solved now
+    pending: VecDeque<(Board, Board)>,
+    current: Option<Segment>,
+
+    // Moves found so far, from the start
+    path: Vec<Move>,
+
+    // Nodes expanded by passes that have finished
+    finished_nodes: usize
+}
+
+impl FrontierAStar {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board, kind: HeuristicKind) -> Result<FrontierAStar, SolveError> {
+        if start_board.size > MAX_PACKED_SIZE {
+            return Err(SolveError::InvalidBoard(format!("frontier search only packs boards up to {}x{}", MAX_PACKED_SIZE, MAX_PACKED_SIZE)));
+        }
+
+        let mut pending = VecDeque::new();
+        pending.push_back((copy(start_board), copy(goal_board)));
+
+        Ok(FrontierAStar {
+            kind,
+            pending,
+            current: None,
+            path: Vec::new(),
+            finished_nodes: 0
+        })
+    }
+
+    fn start_segment(&self, start: Board, goal: Board) -> Segment {
+        let estimate = GoalDistance::with_kind(&goal, self.kind).estimate(&start.state);
+        if estimate < SPLIT_THRESHOLD {
+            return Segment::Direct(IdaStar::new(&start, &goal, self.kind));
+        }
+
+        let pass = FrontierPass::new(&start, &goal, self.kind, estimate / 2);
+        Segment::Split(start, goal, pass)
+    }
+
+    fn current_nodes(&self) -> usize {
+        match &self.current {
+            Some(Segment::Split(_, _, pass)) => pass.nodes_expanded,
+            Some(Segment::Direct(search)) => search.nodes_expanded(),
+            None => 0
+        }
+    }
+}
+
+fn copy(board: &Board) -> Board {
+    Board::new(board.size, board.state.clone())
+}
+
+impl Search for FrontierAStar {
+    // Exhausted if the goal can't be reached and the start is estimated at least SPLIT_THRESHOLD
+    // moves from it. Closer than that the board goes straight to IDA*, which never finishes, so
+    // the caller must make sure the board is solvable
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded().saturating_add(budget);
+
+        loop {
+            if self.current.is_none() {
+                let (start, goal) = match self.pending.pop_front() {
+                    Some(segment) => segment,
+                    None => return SearchStatus::Solved(self.path.clone())
+                };
+                self.current = Some(self.start_segment(start, goal));
+            }
+
+            let nodes_expanded = self.nodes_expanded();
+            if nodes_expanded >= target {
+                return SearchStatus::InProgress;
+            }
+            let budget = target - nodes_expanded;
+
+            match self.current.as_mut().expect("set above") {
+                Segment::Direct(search) => {
+                    if let SearchStatus::Solved(moves) = search.step(budget) {
+                        self.finished_nodes += search.nodes_expanded();
+                        self.path.extend(moves);
+                        self.current = None;
+                    }
+                },
+                Segment::Split(start, goal, pass) => {
+                    match pass.step(budget) {
+                        PassStatus::Found(relay) => {
+                            self.finished_nodes += pass.nodes_expanded;
+
+                            // Solve up to the relay board and then on from it, ahead of anything else left
+                            let relay = Board::new(start.size, unpack(relay, start.state.len()));
+                            self.pending.push_front((copy(&relay), copy(goal)));
+                            self.pending.push_front((copy(start), relay));
+                            self.current = None;
+                        },
+                        // Only the first segment can fail to reach its goal, every relay board
+                        // is on a path to the goal
+                        PassStatus::Exhausted => return SearchStatus::Exhausted,
+                        PassStatus::InProgress => ()
+                    }
+                }
+            }
+        }
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.finished_nodes + self.current_nodes()
+    }
+
+    fn stored_states(&self) -> usize {
+        match &self.current {
+            Some(Segment::Split(_, _, pass)) => pass.frontier.len(),
+            Some(Segment::Direct(search)) => search.stored_states(),
+            None => 0
+        }
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        match &self.current {
+            Some(Segment::Split(_, _, pass)) => pass.open.peek().map(|(Reverse(f), _, _)| *f as usize),
+            Some(Segment::Direct(search)) => search.f_bound(),
+            None => None
+        }
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        match &self.current {
+            Some(Segment::Split(_, _, pass)) => vec![pass.frontier.len()],
+            Some(Segment::Direct(search)) => search.frontier_sizes(),
+            None => vec![0]
+        }
+    }
+
+    // The pieces of the path solved so far, all from the start
+    fn best_path(&self) -> Vec<Move> {
+        self.path.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // 4x4 boards a random walk from the goal, estimated far enough out to be split at least once
+    fn split_boards(goal: &Board, count: usize) -> Vec<Board> {
+        let heuristic = GoalDistance::with_kind(goal, HeuristicKind::LinearConflict);
+        let mut rng = ChaCha8Rng::seed_from_u64(46);
+        let mut boards = Vec::new();
+
+        while boards.len() < count {
+            let mut board = copy(goal);
+            for _ in 0..40 {
+                if let Some(next) = board.apply_move(MOVES[rng.gen_range(0..MOVES.len())]) {
+                    board = next;
+                }
+            }
+            if heuristic.estimate(&board.state) >= SPLIT_THRESHOLD {
+                boards.push(board);
+            }
+        }
+        boards
+    }
+
+    fn run(search: &mut dyn Search) -> Vec<Move> {
+        match search.step(usize::MAX) {
+            SearchStatus::Solved(path) => path,
+            _ => panic!("solvable boards are solved")
+        }
+    }
+
+    #[test]
+    fn split_searches_find_optimal_paths() {
+        let goal = Board::goal_board(4);
+
+        for board in split_boards(&goal, 5) {
+            let path = run(&mut FrontierAStar::new(&board, &goal, HeuristicKind::LinearConflict).unwrap());
+            let optimal = run(&mut IdaStar::new(&board, &goal, HeuristicKind::LinearConflict));
+
+            assert_eq!(board.apply_moves(&path).unwrap().state, goal.state);
+            assert_eq!(path.len(), optimal.len(), "{:?}", board.state);
+
+            // A relay depth of 0 is never reached, so this pass doesn't split the path
+            let mut pass = FrontierPass::new(&board, &goal, HeuristicKind::LinearConflict, 0);
+            assert!(matches!(pass.step(usize::MAX), PassStatus::Found(NO_RELAY)));
+            assert_eq!(pass.frontier[&pass.goal].g as usize, path.len(), "{:?}", board.state);
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_exhaust_a_pass() {
+        let start = Board::new(2, vec![2, 1, 3, 0]);
+        let mut pass = FrontierPass::new(&start, &Board::goal_board(2), HeuristicKind::LinearConflict, 1);
+        assert!(matches!(pass.step(usize::MAX), PassStatus::Exhausted));
+    }
+}
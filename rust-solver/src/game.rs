@@ -0,0 +1,288 @@
+use super::{Board, Move, Slide, SolveError};
+use super::solver::check_boards;
+
+// A game in progress: the board the player is looking at, the moves they have made so far and
+// how long they have been playing.
+//
+// The session doesn't read a clock itself, every call that can start or stop the timer takes the
+// current time in milliseconds from the caller (ex. Date.now() in the browser). The timer starts
+// on the first move and stops when the goal is reached.
+pub struct GameSession {
+    start: Board,
+    goal: Board,
+    board: Board,
+
+    // Moves of the blank square, in the order they were made
+    history: Vec<Move>,
+
+    // Moves taken back by undo, most recent last
+    undone: Vec<Move>,
+
+    started_at: Option<f64>,
+    solved_at: Option<f64>
+}
+
+impl GameSession {
+    pub fn new(start_board: &Board, goal_board: &Board) -> Result<GameSession, SolveError> {
+        check_boards(start_board, goal_board)?;
+
+        Ok(GameSession {
+            start: Board::new(start_board.size, start_board.state.clone()),
+            goal: Board::new(goal_board.size, goal_board.state.clone()),
+            board: Board::new(start_board.size, start_board.state.clone()),
+            history: Vec::new(),
+            undone: Vec::new(),
+            started_at: None,
+            solved_at: None
+        })
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn goal(&self) -> &Board {
+        &self.goal
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.history.len()
+    }
+
+    // Moves so far in the slide-tile metric, where a run of tiles slid along one line is one move
+    pub fn slide_count(&self) -> usize {
+        Slide::moves_to_slides(&self.history).len()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.board == self.goal
+    }
+
+    // Move the blank square, false if it would leave the board. Clears the redo history
+    pub fn apply_move(&mut self, the_move: Move, now_ms: f64) -> bool {
+        if the_move == Move::None || !self.slide(the_move, now_ms) {
+            return false;
+        }
+
+        self.history.push(the_move);
+        self.undone.clear();
+        true
+    }
+
+    // Move the tile at index towards the blank square. Any tile in the same row or column as the
+    // blank can be clicked, the tiles between them all slide along and each counts as a move
+    // (the whole click is one move in slide_count). Returns the number of moves made, 0 if the
+    // tile can't move
+    pub fn click_tile(&mut self, index: usize, now_ms: f64) -> usize {
+        let size  = self.board.size;
+        let blank = self.board.get_index_of(&0);
+
+        if index >= self.board.state.len() || index == blank {
+            return 0;
+        }
+
+        let (the_move, distance) = if index % size == blank % size {
+            if index < blank { (Move::Up, (blank - index) / size) } else { (Move::Down, (index - blank) / size) }
+        }
+        else if index / size == blank / size {
+            if index < blank { (Move::Left, blank - index) } else { (Move::Right, index - blank) }
+        }
+        else {
+            return 0;
+        };
+
+        for _ in 0..distance {
+            self.apply_move(the_move, now_ms);
+        }
+        distance
+    }
+
+    // Take back the last move, false if there is nothing to undo
+    pub fn undo(&mut self, now_ms: f64) -> bool {
+        match self.history.pop() {
+            Some(the_move) => {
+                self.slide(the_move.inverse(), now_ms);
+                self.undone.push(the_move);
+                true
+            },
+            None => false
+        }
+    }
+
+    // Make the last undone move again, false if there is nothing to redo
+    pub fn redo(&mut self, now_ms: f64) -> bool {
+        match self.undone.pop() {
+            Some(the_move) => {
+                self.slide(the_move, now_ms);
+                self.history.push(the_move);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    // Back to the start board with no history and the timer stopped
+    pub fn reset(&mut self) {
+        self.board = self.start.clone();
+        self.history.clear();
+        self.undone.clear();
+        self.started_at = None;
+        self.solved_at = None;
+    }
+
+    // Time played, 0 before the first move. Frozen once the goal is reached
+    pub fn elapsed_ms(&self, now_ms: f64) -> f64 {
+        match self.started_at {
+            Some(started_at) => (self.solved_at.unwrap_or(now_ms) - started_at).max(0.0),
+            None => 0.0
+        }
+    }
+
+    // Move the blank and keep the timer up to date, without touching the history
+    fn slide(&mut self, the_move: Move, now_ms: f64) -> bool {
+        let next = match self.board.apply_move(the_move) {
+            Some(next) => next,
+            None => return false
+        };
+
+        // Drop the solver bookkeeping (move list, cost) that apply_move builds up
+        self.board = Board::new(next.size, next.state);
+
+        if self.started_at.is_none() {
+            self.started_at = Some(now_ms);
+        }
+        self.solved_at = match self.solved_at {
+            Some(solved_at) if self.is_solved() => Some(solved_at),
+            _ if self.is_solved() => Some(now_ms),
+            _ => None
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two moves from the 3x3 goal, the blank is in the bottom left corner
+    fn session() -> GameSession {
+        let goal  = Board::goal_board(3);
+        let start = goal.apply_moves(&[Move::Left, Move::Left]).unwrap();
+        GameSession::new(&start, &goal).unwrap()
+    }
+
+    #[test]
+    fn clicks_only_move_tiles_in_line_with_the_blank() {
+        let mut game = session();
+        let start = game.board().state.clone();
+
+        // Diagonal from the blank, the blank itself and off the board
+        assert_eq!(game.click_tile(4, 0.0), 0);
+        assert_eq!(game.click_tile(6, 0.0), 0);
+        assert_eq!(game.click_tile(9, 0.0), 0);
+        assert_eq!(game.board().state, start);
+        assert_eq!(game.move_count(), 0);
+
+        // The top left tile slides the whole column down
+        assert_eq!(game.click_tile(0, 0.0), 2);
+        assert_eq!(game.board().state, vec![0, 2, 3, 1, 5, 6, 4, 7, 8]);
+        assert_eq!(game.history(), &[Move::Up, Move::Up]);
+        assert_eq!(game.move_count(), 2);
+        assert_eq!(game.slide_count(), 1);
+
+        assert_eq!(game.click_tile(1, 0.0), 1);
+        assert_eq!(game.move_count(), 3);
+        assert_eq!(game.slide_count(), 2);
+    }
+
+    #[test]
+    fn solving_is_detected() {
+        let mut game = session();
+        assert!(!game.is_solved());
+
+        assert_eq!(game.click_tile(8, 0.0), 2);
+        assert!(game.is_solved());
+        assert_eq!(game.move_count(), 2);
+
+        assert!(game.apply_move(Move::Up, 0.0));
+        assert!(!game.is_solved());
+        assert!(!game.apply_move(Move::Right, 0.0));
+        assert!(!game.apply_move(Move::None, 0.0));
+        assert_eq!(game.move_count(), 3);
+    }
+
+    #[test]
+    fn undo_and_redo_retrace_the_moves() {
+        let mut game = session();
+        assert!(!game.undo(0.0));
+        assert!(!game.redo(0.0));
+
+        let start = game.board().state.clone();
+        game.apply_move(Move::Right, 0.0);
+        let after_first = game.board().state.clone();
+        game.apply_move(Move::Right, 0.0);
+        assert!(game.is_solved());
+
+        assert!(game.undo(0.0));
+        assert_eq!(game.board().state, after_first);
+        assert!(game.undo(0.0));
+        assert_eq!(game.board().state, start);
+        assert!(!game.can_undo());
+        assert_eq!(game.move_count(), 0);
+
+        assert!(game.redo(0.0));
+        assert_eq!(game.board().state, after_first);
+        assert_eq!(game.move_count(), 1);
+        assert!(game.can_redo());
+
+        // A new move throws away what was left to redo
+        assert!(game.apply_move(Move::Up, 0.0));
+        assert!(!game.can_redo());
+        assert!(!game.redo(0.0));
+        assert_eq!(game.history(), &[Move::Right, Move::Up]);
+    }
+
+    #[test]
+    fn reset_goes_back_to_the_start() {
+        let mut game = session();
+        let start = game.board().state.clone();
+
+        game.click_tile(0, 1000.0);
+        game.undo(2000.0);
+        game.reset();
+
+        assert_eq!(game.board().state, start);
+        assert_eq!(game.move_count(), 0);
+        assert!(!game.can_undo());
+        assert!(!game.can_redo());
+        assert_eq!(game.elapsed_ms(5000.0), 0.0);
+    }
+
+    #[test]
+    fn the_timer_runs_from_the_first_move_until_solved() {
+        let mut game = session();
+        assert_eq!(game.elapsed_ms(1000.0), 0.0);
+
+        game.apply_move(Move::Right, 1000.0);
+        assert_eq!(game.elapsed_ms(1500.0), 500.0);
+
+        game.apply_move(Move::Right, 3000.0);
+        assert_eq!(game.elapsed_ms(9000.0), 2000.0);
+    }
+}
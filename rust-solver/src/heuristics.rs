@@ -1,75 +1,153 @@
-
 use super::Board;
 
-pub struct Heuristic {
-    start_board: Board,
-    gweight : i32,
-    hweight : i32
+// Which admissible estimate GoalDistance uses
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeuristicKind {
+    // Sum of each tile's row and column distance from its goal position
+    Manhattan,
+
+    // Manhattan plus two moves for every tile that has to leave its row or column to let
+    // another tile in the same line past it
+    LinearConflict
 }
 
-impl Heuristic {
-    pub fn new(start_board: Board) -> Heuristic {
-        Heuristic {
-            start_board: start_board,
-            gweight: 11,
-            hweight: 3
+impl HeuristicKind {
+    pub fn from_name(name: &str) -> Option<HeuristicKind> {
+        match name {
+            "manhattan"       => Some(HeuristicKind::Manhattan),
+            "linear-conflict" => Some(HeuristicKind::LinearConflict),
+            _ => None
         }
     }
 
-    pub fn calculate_move_cost(&self, current_board: &Board) -> i32 {
-        let hval = calculate_manhattan_dist(&current_board);
-        let gval = calculate_g_val(&self.start_board, &current_board);
-
-        self.gweight * gval + self.hweight * hval
+    pub fn name(self) -> &'static str {
+        match self {
+            HeuristicKind::Manhattan      => "manhattan",
+            HeuristicKind::LinearConflict => "linear-conflict"
+        }
     }
 }
 
-/*
-fn calculate_simple_move_cost(current_board: &Board) -> i32 {
-    //current_board.cost + calculate_manhattan_dist(current_board)
-    calculate_manhattan_dist(current_board)
+// Admissible estimate of the number of moves left to reach a goal board, used by the optimal
+// solvers. The blank square isn't counted (which would overestimate) and the goal can be any
+// layout, not just the standard one
+pub struct GoalDistance {
+    size: usize,
+    kind: HeuristicKind,
+
+    // Index of each tile in the goal layout, indexed by tile value
+    goal_positions: Vec<usize>
 }
-*/
 
-fn calculate_g_val(start_board: &Board, current_board: & Board) -> i32 {
-    let mut distance = 0;
-    let mut index    = 0;
-    let size = start_board.size as i32;
-    for tile in &current_board.state {
-        let start_index = start_board.get_index_of(tile) as i32;
-        let start_row   = start_index / size;
-        let start_col   = start_index % size;
-        let cur_row     = index / size;
-        let cur_col     = index % size;
-
-        distance += i32::abs(start_row - cur_row) + i32::abs(start_col - cur_col);
-        index += 1;
+impl GoalDistance {
+    pub fn new(goal_board: &Board) -> GoalDistance {
+        GoalDistance::with_kind(goal_board, HeuristicKind::Manhattan)
     }
-    distance
-}
 
-fn calculate_manhattan_dist(board: &Board) -> i32 {
-    let mut distance = 0;
-    let mut index    = 0;
-    let last_cell    = 15;
+    pub fn with_kind(goal_board: &Board, kind: HeuristicKind) -> GoalDistance {
+        let mut goal_positions = vec![0; goal_board.state.len()];
+        for (index, tile) in goal_board.state.iter().enumerate() {
+            goal_positions[*tile as usize] = index;
+        }
+
+        GoalDistance {
+            size: goal_board.size,
+            kind,
+            goal_positions
+        }
+    }
+
+    pub fn estimate(&self, state: &[u8]) -> i32 {
+        let mut distance = 0;
+        for (index, tile) in state.iter().enumerate() {
+            if *tile != 0 {
+                distance += self.tile_distance(*tile, index);
+            }
+        }
 
-    for tile in &board.state {
-        if *tile != 0 {
-            distance += calculate_manhattan_dist_tile(index, *tile-1, board.size as i32);
+        match self.kind {
+            HeuristicKind::Manhattan      => distance,
+            HeuristicKind::LinearConflict => distance + self.linear_conflicts(state)
         }
-        else {
-            distance += calculate_manhattan_dist_tile(index, last_cell, board.size as i32);
+    }
+
+    // Estimate for a board where `tile` just slid from `from` to `to`, given the estimate before
+    // the slide. Manhattan distance only changes for the one tile, anything else is recomputed
+    pub fn estimate_after_slide(&self, state: &[u8], previous: i32, tile: u8, from: usize, to: usize) -> i32 {
+        match self.kind {
+            HeuristicKind::Manhattan => previous + self.tile_distance(tile, to) - self.tile_distance(tile, from),
+            _ => self.estimate(state)
         }
-        index += 1;
     }
-    distance
+
+    // Manhattan distance of a single tile at the given index from its goal position
+    pub fn tile_distance(&self, tile: u8, index: usize) -> i32 {
+        let goal = self.goal_positions[tile as usize];
+        let rows = (index / self.size) as i32 - (goal / self.size) as i32;
+        let cols = (index % self.size) as i32 - (goal % self.size) as i32;
+
+        rows.abs() + cols.abs()
+    }
+
+    // Index of the tile in the goal layout
+    pub fn goal_position(&self, tile: u8) -> usize {
+        self.goal_positions[tile as usize]
+    }
+
+    fn linear_conflicts(&self, state: &[u8]) -> i32 {
+        let size = self.size;
+        let mut extra = 0;
+
+        // Goal column (or row) of each tile already in its goal row (or column), in board order
+        let mut line = Vec::with_capacity(size);
+
+        for row in 0..size {
+            line.clear();
+            for col in 0..size {
+                let tile = state[row*size + col];
+                let goal = self.goal_positions[tile as usize];
+                if tile != 0 && goal / size == row {
+                    line.push(goal % size);
+                }
+            }
+            extra += 2 * conflict_removals(&line);
+        }
+
+        for col in 0..size {
+            line.clear();
+            for row in 0..size {
+                let tile = state[row*size + col];
+                let goal = self.goal_positions[tile as usize];
+                if tile != 0 && goal % size == col {
+                    line.push(goal / size);
+                }
+            }
+            extra += 2 * conflict_removals(&line);
+        }
+
+        extra
+    }
 }
 
-fn calculate_manhattan_dist_tile(index: i32, value: u8, size: i32) -> i32 {
-    let current_row = index / size;
-    let current_col = index % size;
-    let goal_row    = value as i32 / size;
-    let goal_col    = value as i32 % size;
+// Fewest tiles that have to leave a line so the rest are in goal order, which is everything
+// outside the longest increasing run of goal positions. Each of those tiles needs at least two
+// moves more than its Manhattan distance (out of the line and back in)
+fn conflict_removals(goals: &[usize]) -> i32 {
+    let mut longest = vec![1; goals.len()];
+    for index in 0..goals.len() {
+        for previous in 0..index {
+            if goals[previous] < goals[index] {
+                longest[index] = longest[index].max(longest[previous] + 1);
+            }
+        }
+    }
+
+    (goals.len() - longest.iter().copied().max().unwrap_or(0)) as i32
+}
 
-    i32::abs(goal_row - current_row) + i32::abs(goal_col - current_col)
+/*
+fn calculate_simple_move_cost(current_board: &Board) -> i32 {
+    //current_board.cost + calculate_manhattan_dist(current_board)
+    calculate_manhattan_dist(current_board)
 }
+*/
@@ -0,0 +1,135 @@
+use super::{Board, Move, SolveError};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::ida_star::IdaStar;
+use super::search::{Search, SearchStatus};
+use super::solver::check_boards;
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Nodes an optimal search gets before falling back to lookahead. A few milliseconds of work,
+// enough to solve most 3x3 boards and easy 4x4 boards exactly
+pub const DEFAULT_HINT_BUDGET: usize = 100_000;
+
+// How many moves ahead the fallback looks when the optimal search runs out of budget
+const LOOKAHEAD_DEPTH: usize = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    // Move::None when the board is already at the goal
+    pub best_move: Move,
+
+    // Moves left to the goal. Exact when the board was solved optimally, otherwise a lower
+    // bound on the real distance
+    pub distance: usize,
+    pub exact: bool
+}
+
+// Suggests the next move towards the goal. Tries an optimal search first, if that doesn't
+// finish within budget_nodes the move with the best lookahead estimate is suggested instead
+pub fn hint(board: &Board, goal_board: &Board, budget_nodes: usize) -> Result<Hint, SolveError> {
+    check_boards(board, goal_board)?;
+
+    let mut search = IdaStar::new(board, goal_board, HeuristicKind::Manhattan);
+    if let SearchStatus::Solved(path) = search.step(budget_nodes) {
+        return Ok(Hint {
+            best_move: path.first().copied().unwrap_or(Move::None),
+            distance: path.len(),
+            exact: true
+        });
+    }
+
+    let heuristic = GoalDistance::with_kind(goal_board, HeuristicKind::LinearConflict);
+    let mut best_move  = Move::None;
+    let mut best_value = i32::MAX;
+
+    for the_move in MOVES.iter() {
+        if let Some(child) = board.apply_move(*the_move) {
+            let value = 1 + lookahead(&child, *the_move, LOOKAHEAD_DEPTH - 1, &heuristic);
+            if value < best_value {
+                best_value = value;
+                best_move  = *the_move;
+            }
+        }
+    }
+
+    // Both the lookahead value and the last IDA* threshold never overestimate, keep the larger
+    Ok(Hint {
+        best_move,
+        distance: best_value.max(search.bound()) as usize,
+        exact: false
+    })
+}
+
+// Smallest moves + estimate over every board reachable in up to depth moves, without undoing
+// the previous move
+fn lookahead(board: &Board, previous: Move, depth: usize, heuristic: &GoalDistance) -> i32 {
+    let estimate = heuristic.estimate(&board.state);
+    if depth == 0 || estimate == 0 {
+        return estimate;
+    }
+
+    let mut best = i32::MAX;
+    for the_move in MOVES.iter() {
+        if *the_move == previous.inverse() {
+            continue;
+        }
+        if let Some(child) = board.apply_move(*the_move) {
+            best = best.min(1 + lookahead(&child, *the_move, depth - 1, heuristic));
+        }
+    }
+
+    // A dead end (only possible on tiny boards), fall back to the estimate itself
+    if best == i32::MAX {
+        return estimate;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::DistanceTable;
+    use crate::ranking::{solvable_state_count, unrank_solvable};
+
+    #[test]
+    fn exact_hints_match_the_distance_table() {
+        let goal  = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+        assert_eq!(hint(&goal, &goal, DEFAULT_HINT_BUDGET).unwrap(), Hint { best_move: Move::None, distance: 0, exact: true });
+
+        for index in (0..solvable_state_count(3).unwrap()).step_by(997) {
+            let board = Board::new(3, unrank_solvable(index, 3));
+            let distance = table.distance(&board).unwrap();
+            let hint = hint(&board, &goal, DEFAULT_HINT_BUDGET).unwrap();
+
+            assert!(hint.exact);
+            assert_eq!(hint.distance, distance);
+            if distance == 0 {
+                assert_eq!(hint.best_move, Move::None);
+            }
+            else {
+                let next = board.apply_move(hint.best_move).unwrap();
+                assert_eq!(table.distance(&next), Some(distance - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn running_out_of_budget_gives_a_lower_bound() {
+        let goal  = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+
+        for index in (1..solvable_state_count(3).unwrap()).step_by(997) {
+            let board = Board::new(3, unrank_solvable(index, 3));
+            let distance = table.distance(&board).unwrap();
+            if distance < 8 {
+                continue;
+            }
+
+            let hint = hint(&board, &goal, 10).unwrap();
+            assert!(!hint.exact);
+            assert!(hint.distance <= distance);
+            assert!(board.apply_move(hint.best_move).is_some());
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use super::{Algorithm, Board, Metrics, Move, SolverConfig, SolverSession};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::pdb::{AdditivePatterns, PatternTracker};
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// What IDA* estimates the moves left with
+enum Estimate {
+    Distance(GoalDistance),
+
+    // Following the board being searched, it only changes a tile at a time
+    Patterns(Arc<AdditivePatterns>, PatternTracker)
+}
+
+impl Estimate {
+    fn estimate(&self, state: &[u8]) -> i32 {
+        match self {
+            Estimate::Distance(heuristic)         => heuristic.estimate(state),
+            Estimate::Patterns(patterns, tracker) => tracker.estimate(patterns)
+        }
+    }
+
+    fn estimate_after_slide(&mut self, state: &[u8], previous: i32, tile: u8, from: usize, to: usize) -> i32 {
+        match self {
+            Estimate::Distance(heuristic)         => heuristic.estimate_after_slide(state, previous, tile, from, to),
+            Estimate::Patterns(patterns, tracker) => tracker.slide(patterns, tile, to)
+        }
+    }
+}
+
+// Iterative deepening A*. Memory use is just the current path, and with an admissible
+// heuristic the first solution found is optimal (fewest moves).
+//
+// The depth first search keeps an explicit stack (the path plus the next move to try at each
+// depth) rather than recursing, the board is updated in place as moves are made and undone.
+pub(crate) struct IdaStar {
+    size: usize,
+    tiles: Vec<u8>,
+    blank: usize,
+
+    // Checked whenever the estimate is 0, pattern databases that leave tiles out can be 0 elsewhere
+    goal: Vec<u8>,
+
+    heuristic: Estimate,
+    hval: i32,
+
+    // Iteration threshold, and the smallest f value seen above it (the next threshold)
+    bound: i32,
+    next_bound: i32,
+
+    path: Vec<Move>,
+
+    // Index into MOVES of the next move to try, one entry per depth of the current path
+    next_move: Vec<usize>,
+
+    // Path to the board with the smallest estimate seen so far, and that estimate
+    best_path: Vec<Move>,
+    best_h: i32,
+
+    nodes_expanded: usize
+}
+
+impl IdaStar {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board, kind: HeuristicKind) -> IdaStar {
+        IdaStar::with_estimate(start_board, goal_board, Estimate::Distance(GoalDistance::with_kind(goal_board, kind)))
+    }
+
+    // Estimates with pattern databases, which have to be for the goal being solved to
+    pub(crate) fn with_patterns(start_board: &Board, goal_board: &Board, patterns: Arc<AdditivePatterns>) -> IdaStar {
+        let tracker = patterns.track(&start_board.state);
+        IdaStar::with_estimate(start_board, goal_board, Estimate::Patterns(patterns, tracker))
+    }
+
+    fn with_estimate(start_board: &Board, goal_board: &Board, heuristic: Estimate) -> IdaStar {
+        let hval = heuristic.estimate(&start_board.state);
+
+        IdaStar {
+            size: start_board.size,
+            tiles: start_board.state.clone(),
+            blank: start_board.get_index_of(&0),
+            goal: goal_board.state.clone(),
+            heuristic,
+            hval,
+            bound: hval,
+            next_bound: i32::MAX,
+            path: Vec::new(),
+            next_move: vec![0],
+            best_path: Vec::new(),
+            best_h: hval,
+            nodes_expanded: 0
+        }
+    }
+
+    // Lower bound on the solution length, the threshold of the current iteration
+    pub(crate) fn bound(&self) -> i32 {
+        self.bound
+    }
+
+    fn can_slide(&self, the_move: Move) -> bool {
+        match the_move {
+            Move::Up    => self.blank >= self.size,
+            Move::Down  => self.blank / self.size < self.size - 1,
+            Move::Left  => !self.blank.is_multiple_of(self.size),
+            Move::Right => self.blank % self.size < self.size - 1,
+            Move::None  => false
+        }
+    }
+
+    // Move the blank, updating the heuristic value for the one tile that moves
+    fn slide(&mut self, the_move: Move) {
+        let target = match the_move {
+            Move::Up    => self.blank - self.size,
+            Move::Down  => self.blank + self.size,
+            Move::Left  => self.blank - 1,
+            Move::Right => self.blank + 1,
+            Move::None  => return
+        };
+
+        let tile = self.tiles[target];
+        self.tiles[self.blank] = tile;
+        self.tiles[target] = 0;
+
+        self.hval  = self.heuristic.estimate_after_slide(&self.tiles, self.hval, tile, target, self.blank);
+        self.blank = target;
+    }
+}
+
+impl Search for IdaStar {
+    // Never finishes if the goal can't be reached, the caller must make sure the board is solvable
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        loop {
+            if self.hval == 0 && self.tiles == self.goal {
+                return SearchStatus::Solved(self.path.clone());
+            }
+            if self.nodes_expanded >= target {
+                return SearchStatus::InProgress;
+            }
+
+            let depth = self.path.len();
+            let move_index = self.next_move[depth];
+
+            if move_index == MOVES.len() {
+                // Every child of this board has been tried
+                if depth == 0 {
+                    self.bound = self.next_bound;
+                    self.next_bound = i32::MAX;
+                    self.next_move[0] = 0;
+                }
+                else {
+                    self.next_move.pop();
+                    let last = self.path.pop().unwrap();
+                    self.slide(last.inverse());
+                }
+                continue;
+            }
+
+            self.next_move[depth] += 1;
+            let the_move = MOVES[move_index];
+
+            // Never undo the move that got us here
+            if self.path.last() == Some(&the_move.inverse()) || !self.can_slide(the_move) {
+                continue;
+            }
+
+            self.slide(the_move);
+            self.nodes_expanded += 1;
+
+            let fval = depth as i32 + 1 + self.hval;
+            if fval > self.bound {
+                self.next_bound = self.next_bound.min(fval);
+                self.slide(the_move.inverse());
+                continue;
+            }
+
+            self.path.push(the_move);
+            if self.hval < self.best_h {
+                self.best_h = self.hval;
+                self.best_path = self.path.clone();
+            }
+            self.next_move.push(0);
+        }
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    // Only the current path is kept
+    fn stored_states(&self) -> usize {
+        self.path.len() + 1
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        Some(self.bound as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.path.len()]
+    }
+
+    fn best_path(&self) -> Vec<Move> {
+        self.best_path.clone()
+    }
+}
+
+pub fn ida_star_solver(start_board: &Board, goal_board: &Board) -> Vec<Move> {
+    ida_star_solver_with_metrics(start_board, goal_board).path
+}
+
+pub fn ida_star_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::IdaStar, heuristic: HeuristicKind::Manhattan, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
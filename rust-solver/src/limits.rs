@@ -0,0 +1,55 @@
+use std::fmt;
+
+use web_time::Duration;
+
+// Caps on how much work a solve may do, none are set by default. A solve that reaches one stops
+// with SolveError::BudgetExhausted, carrying the metrics so far and the best partial path
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub max_nodes: Option<usize>,
+
+    // Boards held in memory at once (open and closed lists, or the current path for IDA*)
+    pub max_stored_states: Option<usize>,
+
+    // Time spent inside SolverSession::step, not wall clock time since the session was created.
+    // A session stepped from idle callbacks only uses up its limit while it is actually searching
+    pub time_limit: Option<Duration>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    StoredStates,
+    Time
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Nodes        => write!(f, "node"),
+            Limit::StoredStates => write!(f, "stored state"),
+            Limit::Time         => write!(f, "time")
+        }
+    }
+}
+
+impl SearchLimits {
+    // The first limit the search has reached, if any
+    pub fn reached(&self, nodes_expanded: usize, stored_states: usize, running_time: Duration) -> Option<Limit> {
+        if self.max_nodes.is_some_and(|max| nodes_expanded >= max) {
+            return Some(Limit::Nodes);
+        }
+        if self.max_stored_states.is_some_and(|max| stored_states >= max) {
+            return Some(Limit::StoredStates);
+        }
+        if self.time_limit.is_some_and(|limit| running_time >= limit) {
+            return Some(Limit::Time);
+        }
+        None
+    }
+
+    // Nodes left before the node limit, usize::MAX without one
+    pub fn nodes_left(&self, nodes_expanded: usize) -> usize {
+        self.max_nodes.map_or(usize::MAX, |max| max.saturating_sub(nodes_expanded))
+    }
+}
@@ -0,0 +1,98 @@
+use super::{Board, Move, SolveError};
+use super::analysis::DistanceTable;
+use super::solver::check_boards;
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Optimal path to the table's goal by greedy descent: every board but the goal has a neighbour
+// one move closer, so stepping to it each time takes exactly the table's distance in moves. Only
+// a handful of rankings per move, so it takes microseconds. The table can be built with
+// DistanceTable::build, or loaded from a file saved by it (ex. by the 'analyze' command). A
+// corrupt table gives SolveError::InvalidTable once the descent gets stuck, DistanceTable::verify
+// checks a whole table up front but has to search the board space again to do it. Boards are
+// square so tables cover 2x2 and 3x3, rectangular boards such as 2x3 aren't supported
+pub fn lookup_solver(table: &DistanceTable, start_board: &Board) -> Result<Vec<Move>, SolveError> {
+    check_boards(start_board, table.goal())?;
+
+    let mut board = Board::new(start_board.size, start_board.state.clone());
+    let mut distance = table.distance(&board)
+        .ok_or_else(|| SolveError::InvalidTable(format!("no entry for {:?}", board.state)))?;
+    let mut path = Vec::with_capacity(distance);
+
+    while distance > 0 {
+        let (the_move, next) = MOVES.iter()
+            .filter_map(|the_move| board.apply_move(*the_move).map(|next| (*the_move, next)))
+            .find(|(_, next)| table.distance(next) == Some(distance - 1))
+            .ok_or_else(|| SolveError::InvalidTable(format!("{:?} is {} moves from the goal but no neighbour is closer", board.state, distance)))?;
+
+        path.push(the_move);
+        board = Board::new(next.size, next.state);
+        distance -= 1;
+    }
+
+    if board != *table.goal() {
+        return Err(SolveError::InvalidTable(format!("{:?} is recorded as the goal", board.state)));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ranking::{rank_solvable, unrank_solvable};
+
+    #[test]
+    fn saved_tables_load_back_the_same() {
+        for size in 2..=3 {
+            let table = DistanceTable::build(&Board::goal_board(size)).unwrap();
+            let loaded = DistanceTable::from_bytes(&table.to_bytes()).unwrap();
+
+            assert_eq!(loaded.to_bytes(), table.to_bytes());
+            assert_eq!(loaded.goal().state, table.goal().state);
+            assert!(loaded.verify().is_ok());
+        }
+    }
+
+    #[test]
+    fn lookup_paths_take_the_table_distance() {
+        // A goal in the other half of the state space as well, which the table ranks with tiles 1
+        // and 2 swapped
+        let goals = [Board::goal_board(2), Board::goal_board(3), Board::new(3, vec![2, 1, 3, 4, 5, 6, 7, 8, 0])];
+
+        for goal in goals.iter() {
+            let table = DistanceTable::build(goal).unwrap();
+            for index in (0..table.board_count()).step_by(37) {
+                let mut board = Board::new(goal.size, unrank_solvable(index as u128, goal.size));
+                if !board.can_reach(goal) {
+                    let (one, two) = (board.get_index_of(&1), board.get_index_of(&2));
+                    board.state.swap(one, two);
+                }
+
+                let path = lookup_solver(&table, &board).unwrap();
+                assert_eq!(path.len(), table.distance(&board).unwrap(), "{:?}", board.state);
+                assert_eq!(board.apply_moves(&path).unwrap().state, goal.state);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_tables_are_errors() {
+        let goal = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+        let bytes = table.to_bytes();
+        let distances_start = bytes.len() - table.board_count();
+        let board = Board::new(3, vec![1, 2, 3, 4, 5, 6, 0, 7, 8]);
+
+        // Every board recorded as the goal
+        let mut zeroed = bytes.clone();
+        zeroed[distances_start..].iter_mut().for_each(|distance| *distance = 0);
+        let zeroed = DistanceTable::from_bytes(&zeroed).unwrap();
+        assert!(matches!(lookup_solver(&zeroed, &board), Err(SolveError::InvalidTable(_))));
+
+        // One board recorded far further out than its neighbours
+        let mut raised = bytes.clone();
+        raised[distances_start + rank_solvable(&board.state, 3) as usize] = 200;
+        let raised = DistanceTable::from_bytes(&raised).unwrap();
+        assert!(matches!(lookup_solver(&raised, &board), Err(SolveError::InvalidTable(_))));
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use super::{Board, Move};
+use super::analysis::DistanceTable;
+use super::reduction::{Step, check_goal, core_boards, move_pair, neighbour, pair_window, plan};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Start of a saved table, followed by a version byte, the board size, the goal tiles and the
+// number of stages as two bytes. Then each stage's tile and a macro for every cell, each a two
+// byte length and a byte per move, and last the core's distance table as DistanceTable saves it
+const MAGIC: &[u8; 4] = b"SPMT";
+const VERSION: u8 = 1;
+
+// Saved as the length for cells a stage's tile can't start in
+const NO_MACRO: u16 = u16::MAX;
+
+// Macro-operators for solving every board of one size and goal without search. Boards are solved
+// in the reduction solver's order, and each tile is a stage with a table of move sequences indexed
+// by where that tile is. Between stages the blank waits in a fixed cell, so where the tile is
+// decides everything: its macro brings the tile home without touching a placed tile and leaves the
+// blank where the next stage expects it. The first stage brings the blank to that cell, and the
+// last two tiles of each row and column take two stages, ending with the pair sliding home
+// together. The 3x3 corner that's left is read off a distance table.
+//
+// Each stage's macros come from one breadth first search back from its goal, so a 10x10 table is
+// built in about a second. Replaying it looks up one macro per stage, so every board takes time in
+// proportion to the moves, which are never more than max_solution_length
+pub struct MacroTable {
+    goal: Board,
+    stages: Vec<Stage>,
+    core: DistanceTable
+}
+
+// The tile a stage places (0 for the blank) and its macro for each cell the tile can start in
+#[derive(PartialEq)]
+struct Stage {
+    tile: u8,
+    macros: Vec<Option<Vec<Move>>>
+}
+
+impl MacroTable {
+    pub fn build(goal_board: &Board) -> Result<MacroTable, String> {
+        goal_board.validate()?;
+        check_goal(goal_board)?;
+
+        let size = goal_board.size;
+        let goal = &goal_board.state;
+        let steps = plan(size);
+        let mut locked = vec![false; size * size];
+        let mut stages = Vec::new();
+
+        // Where the blank waits between stages
+        let mut rest = 0;
+        if steps.len() > 1 {
+            stages.push(Stage { tile: 0, macros: blank_macros(size, rest) });
+        }
+
+        for step in steps {
+            match step {
+                // The blank waits below the tile just placed
+                Step::Place(cell) => {
+                    let next_rest = neighbour(size, cell, Move::Down).expect("single tiles are never in the bottom row");
+                    stages.push(Stage { tile: goal[cell], macros: tile_macros(size, &locked, rest, (cell, next_rest), &[]) });
+                    locked[cell] = true;
+                    rest = next_rest;
+                },
+
+                // The first tile goes to the end of the line with the blank beside it, where the
+                // second tile then goes, leaving the blank at the start of the line to slide both
+                // home. The second tile can be shut in at the start of the line, those macros move
+                // both tiles together as Reduction does
+                Step::PlacePair { first, second, along, across } => {
+                    let staging = neighbour(size, second, across).expect("the board is bigger than the core");
+                    stages.push(Stage { tile: goal[first], macros: tile_macros(size, &locked, rest, (second, staging), &[]) });
+                    locked[second] = true;
+                    rest = staging;
+
+                    let mut macros = tile_macros(size, &locked, rest, (staging, first), &[along, across]);
+                    let window = pair_window(size, first, second, along, across);
+                    for cell in window.iter().filter(|cell| **cell != second && **cell != rest) {
+                        if macros[*cell].is_none() {
+                            let mut moves = move_pair(size, &window, (second, *cell, rest), |first_at, second_at, blank| (first_at, second_at, blank) == (second, staging, first), &mut 0);
+                            moves.extend_from_slice(&[along, across]);
+                            macros[*cell] = Some(moves);
+                        }
+                    }
+                    stages.push(Stage { tile: goal[second], macros });
+                    locked[first] = true;
+                },
+
+                Step::Core => ()
+            }
+        }
+
+        let (_, core_goal) = core_boards(size, goal, goal);
+        Ok(MacroTable {
+            goal: Board::new(size, goal.clone()),
+            stages,
+            core: DistanceTable::build(&core_goal)?
+        })
+    }
+
+    pub fn goal(&self) -> &Board {
+        &self.goal
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    // Macros over every stage
+    pub fn macro_count(&self) -> usize {
+        self.stages.iter().map(|stage| stage.macros.iter().filter(|moves| moves.is_some()).count()).sum()
+    }
+
+    // The most moves any board takes: the longest macro of each stage and then the core's hardest board
+    pub fn max_solution_length(&self) -> usize {
+        let longest = |stage: &Stage| stage.macros.iter().flatten().map(Vec::len).max().unwrap_or(0);
+        self.stages.iter().map(longest).sum::<usize>() + self.core.max_distance()
+    }
+
+    // The stage's macro for the board the stages before it left
+    pub(crate) fn macro_for(&self, stage: usize, board: &Board) -> Option<&[Move]> {
+        let stage = &self.stages[stage];
+        stage.macros[board.get_index_of(&stage.tile)].as_deref()
+    }
+
+    pub(crate) fn core(&self) -> &DistanceTable {
+        &self.core
+    }
+
+    // The corner left once every stage is done, as a board for the core's distance table
+    pub(crate) fn core_board(&self, board: &Board) -> Board {
+        core_boards(self.goal.size, &board.state, &self.goal.state).0
+    }
+
+    // Checks a table (ex. one loaded from a file) against a fresh build for its goal, naming the
+    // first stage that differs
+    pub fn verify(&self) -> Result<(), String> {
+        let expected = MacroTable::build(&self.goal)?;
+
+        if let Some(stage) = self.stages.iter().zip(&expected.stages).position(|(stage, expected)| stage != expected) {
+            return Err(format!("stage {} (tile {}) doesn't match a fresh build", stage, self.stages[stage].tile));
+        }
+        if self.core.to_bytes() != expected.core.to_bytes() {
+            return Err("the core's distance table doesn't match a fresh build".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.goal.size as u8);
+        bytes.extend_from_slice(&self.goal.state);
+        bytes.extend_from_slice(&(self.stages.len() as u16).to_le_bytes());
+
+        for stage in &self.stages {
+            bytes.push(stage.tile);
+            for moves in &stage.macros {
+                match moves {
+                    Some(moves) => {
+                        bytes.extend_from_slice(&(moves.len() as u16).to_le_bytes());
+                        bytes.extend(moves.iter().map(|the_move| encode_move(*the_move)));
+                    },
+                    None => bytes.extend_from_slice(&NO_MACRO.to_le_bytes())
+                }
+            }
+        }
+
+        bytes.extend(self.core.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<MacroTable, String> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("not a macro table".to_string());
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(format!("unsupported macro table version {}", bytes[MAGIC.len()]));
+        }
+
+        let size = bytes[MAGIC.len() + 1] as usize;
+        let mut reader = Reader { bytes, position: MAGIC.len() + 2 };
+
+        let goal = Board::new(size, reader.take(size * size)?.to_vec());
+        goal.validate().map_err(|err| format!("invalid goal in macro table: {}", err))?;
+        check_goal(&goal).map_err(|err| format!("invalid goal in macro table: {}", err))?;
+
+        let stage_count = reader.read_u16()? as usize;
+        let mut stages = Vec::with_capacity(stage_count);
+        for _ in 0..stage_count {
+            let tile = reader.take(1)?[0];
+            if tile as usize >= size * size {
+                return Err(format!("tile {} in a {}x{} macro table", tile, size, size));
+            }
+
+            let mut macros = Vec::with_capacity(size * size);
+            for _ in 0..size * size {
+                macros.push(match reader.read_u16()? {
+                    NO_MACRO => None,
+                    length => Some(reader.take(length as usize)?.iter().map(|code| decode_move(*code)).collect::<Result<Vec<_>, _>>()?)
+                });
+            }
+            stages.push(Stage { tile, macros });
+        }
+
+        let core = DistanceTable::from_bytes(&bytes[reader.position..])?;
+        let (_, core_goal) = core_boards(size, &goal.state, &goal.state);
+        if *core.goal() != core_goal {
+            return Err("the macro table's core doesn't match its goal".to_string());
+        }
+
+        Ok(MacroTable { goal, stages, core })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|err| format!("unable to write {}: {}", path, err))
+    }
+
+    pub fn load(path: &str) -> Result<MacroTable, String> {
+        let bytes = fs::read(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+        MacroTable::from_bytes(&bytes)
+    }
+}
+
+// Shortest moves from the blank in each cell to the cell `to`, by breadth first search back from it
+fn blank_macros(size: usize, to: usize) -> Vec<Option<Vec<Move>>> {
+    let mut toward = vec![None; size * size];
+    toward[to] = Some(Move::None);
+
+    let mut queue = VecDeque::new();
+    queue.push_back(to);
+
+    while let Some(blank) = queue.pop_front() {
+        for the_move in MOVES.iter() {
+            if let Some(next) = neighbour(size, blank, *the_move) {
+                if toward[next].is_none() {
+                    toward[next] = Some(the_move.inverse());
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    (0..size * size).map(|from| {
+        let mut blank = from;
+        let mut moves = Vec::new();
+        while let Some(the_move) = toward[blank].filter(|the_move| *the_move != Move::None) {
+            moves.push(the_move);
+            blank = neighbour(size, blank, the_move).expect("the search only goes between neighbours");
+        }
+        Some(moves)
+    }).collect()
+}
+
+// Shortest moves from a tile in each cell and the blank at `blank_from` to the tile and the blank
+// at `to`, without touching a locked cell, each followed by the `finish` moves. By breadth first
+// search back from `to` over where the tile and the blank are. None for cells the tile can't be in
+// or can't get out of
+fn tile_macros(size: usize, locked: &[bool], blank_from: usize, to: (usize, usize), finish: &[Move]) -> Vec<Option<Vec<Move>>> {
+    let cells = size * size;
+    let slide = |state: usize, the_move: Move| {
+        let (tile, blank) = (state / cells, state % cells);
+        let next_blank = neighbour(size, blank, the_move).filter(|next| !locked[*next])?;
+        let next_tile = if next_blank == tile { blank } else { tile };
+        Some(next_tile * cells + next_blank)
+    };
+
+    // The move that takes each state one step closer to the target
+    let target = to.0 * cells + to.1;
+    let mut toward = vec![None; cells * cells];
+    toward[target] = Some(Move::None);
+
+    let mut queue = VecDeque::new();
+    queue.push_back(target);
+
+    while let Some(state) = queue.pop_front() {
+        for the_move in MOVES.iter() {
+            if let Some(next) = slide(state, *the_move) {
+                if toward[next].is_none() {
+                    toward[next] = Some(the_move.inverse());
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    (0..cells).map(|from| {
+        if locked[from] || from == blank_from {
+            return None;
+        }
+
+        let mut state = from * cells + blank_from;
+        let mut moves = Vec::new();
+        loop {
+            match toward[state]? {
+                Move::None => break,
+                the_move => {
+                    moves.push(the_move);
+                    state = slide(state, the_move).expect("the search only goes between neighbours");
+                }
+            }
+        }
+        moves.extend_from_slice(finish);
+        Some(moves)
+    }).collect()
+}
+
+fn encode_move(the_move: Move) -> u8 {
+    match the_move {
+        Move::Up    => 0,
+        Move::Down  => 1,
+        Move::Left  => 2,
+        Move::Right => 3,
+        Move::None  => unreachable!("macros are made of real moves")
+    }
+}
+
+fn decode_move(code: u8) -> Result<Move, String> {
+    match code {
+        0 => Ok(Move::Up),
+        1 => Ok(Move::Down),
+        2 => Ok(Move::Left),
+        3 => Ok(Move::Right),
+        _ => Err(format!("invalid move {} in macro table", code))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err("macro table is cut short".to_string());
+        }
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{SolveError, macro_solver};
+
+    #[test]
+    fn saved_tables_load_back_the_same() {
+        for size in [2, 4, 7].iter() {
+            let table = MacroTable::build(&Board::goal_board(*size)).unwrap();
+            let bytes = table.to_bytes();
+
+            let loaded = MacroTable::from_bytes(&bytes).unwrap();
+            assert_eq!(loaded.to_bytes(), bytes);
+            assert!(loaded.stages == table.stages);
+            assert_eq!(loaded.max_solution_length(), table.max_solution_length());
+            if *size == 4 {
+                assert!(loaded.verify().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn damaged_files_are_rejected() {
+        let bytes = MacroTable::build(&Board::goal_board(4)).unwrap().to_bytes();
+
+        assert!(MacroTable::from_bytes(b"SPMT").is_err());
+        assert!(MacroTable::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+        assert!(MacroTable::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(MacroTable::from_bytes(&wrong_magic).is_err());
+    }
+
+    #[test]
+    fn corrupt_macros_are_errors() {
+        let goal = Board::goal_board(4);
+        let bytes = MacroTable::build(&goal).unwrap().to_bytes();
+
+        // The first stage brings the blank to cell 0. From cell 1 that's a single move left, change
+        // it to a move right
+        let cell_1_macro = MAGIC.len() + 2 + 16 + 2 + 1 + 2;
+        assert_eq!(&bytes[cell_1_macro..cell_1_macro + 3], &[1, 0, encode_move(Move::Left)]);
+        let mut corrupt = bytes.clone();
+        corrupt[cell_1_macro + 2] = encode_move(Move::Right);
+
+        let table = MacroTable::from_bytes(&corrupt).unwrap();
+        let start = goal.apply_moves(&[Move::Up, Move::Up, Move::Up, Move::Left, Move::Left]).unwrap();
+        assert_eq!(start.get_index_of(&0), 1);
+        assert!(matches!(macro_solver(&table, &start), Err(SolveError::InvalidTable(_))));
+        assert!(table.verify().is_err());
+
+        // Whatever else gets damaged, replaying either solves the board or says the table is bad
+        let mut rng = ChaCha8Rng::seed_from_u64(50);
+        let boards: Vec<Board> = (0..3).map(|seed| Board::generate_board_seeded(4, seed)).collect();
+        for _ in 0..200 {
+            let mut damaged = bytes.clone();
+            let index = rng.gen_range(MAGIC.len() + 2 + 16..bytes.len());
+            damaged[index] = rng.gen();
+
+            let table = match MacroTable::from_bytes(&damaged) {
+                Ok(table) => table,
+                Err(_) => continue
+            };
+            for board in boards.iter() {
+                match macro_solver(&table, board) {
+                    Ok(path) => assert_eq!(board.apply_moves(&path).unwrap().state, goal.state),
+                    Err(err) => assert!(matches!(err, SolveError::InvalidTable(_)), "{}", err)
+                }
+            }
+        }
+    }
+}
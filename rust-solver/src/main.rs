@@ -1,7 +1,18 @@
 use std::env;
-use std::time::Instant;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use slider_solver_lib::{Board, bidirectional_solver};
+use rand::random;
+
+use slider_solver_lib::{Algorithm, Board, CancelToken, Difficulty, HeuristicKind, Metrics, Move, Progress, SolveError, SolverConfig, DEFAULT_ANYTIME_WEIGHT, anytime_solver, lookup_solver, macro_solver, solve, solve_with_progress};
+use slider_solver_lib::optimize;
+use slider_solver_lib::analysis::DistanceTable;
+use slider_solver_lib::macro_table::MacroTable;
+use slider_solver_lib::pdb::{AdditivePatterns, PARTITION_5X5};
+use slider_solver_lib::pdb_builder::{DEFAULT_BUFFER_STATES, build_pattern_database};
+use slider_solver_lib::regression::{parse_metrics, format_metrics, run_regression};
 
 /*
 fn get_entry(board: &Board, collection: &mut BTreeMap<i32, Vec<Board>>) -> Option<Board> {
@@ -91,21 +102,387 @@ fn solve(start_board : &Board, goal_board : &Board) {
 }
 */
 
-fn parse_args(args: &[String]) -> [u8;16] {
-    let mut state: [u8;16] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+// Tiles in row-major order, the board size is worked out from how many were given
+fn parse_args(args: &[String]) -> Board {
+    let size = (args.len() as f64).sqrt() as usize;
+
+    if size < 2 || size * size != args.len() {
+        panic!("Expected the tiles of a square board (ex. 9 or 16 values), got {}", args.len());
+    }
+
+    let state = args.iter().map(|arg| arg.parse::<u8>().unwrap()).collect();
+
+    Board::new(size, state)
+}
+
+// regress <metrics file> [--ratio <max slowdown>] [--output <updated metrics file>]
+fn regress(args: &[String]) {
+    if args.is_empty() {
+        panic!("Usage: slider_solver regress <metrics file> [--ratio <max slowdown>] [--output <file>]");
+    }
+
+    let input = &args[0];
+    let mut ratio = 1.5;
+    let mut output = format!("{}.new", input);
+
+    let mut index = 1;
+    while index < args.len() {
+        match (args[index].as_str(), args.get(index + 1)) {
+            ("--ratio", Some(value))  => ratio = value.parse::<f64>().expect("Invalid --ratio value"),
+            ("--output", Some(value)) => output = value.clone(),
+            _ => panic!("Unknown or incomplete argument '{}'", args[index])
+        }
+        index += 2;
+    }
+
+    let contents = fs::read_to_string(input).expect("Unable to read metrics file");
+    let baseline = parse_metrics(&contents).unwrap_or_else(|err| panic!("Invalid metrics file: {}", err));
+
+    let results = run_regression(baseline, ratio);
+
+    let mut failures = 0;
+    for result in &results {
+        result.display();
+        if !result.passed() {
+            failures += 1;
+        }
+    }
+
+    let current: Vec<_> = results.into_iter().map(|result| result.current).collect();
+    fs::write(&output, format_metrics(&current)).expect("Unable to write updated metrics file");
+
+    println!("{} of {} boards regressed, updated metrics written to {}", failures, current.len(), output);
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+// generate [size] [--seed <seed>] [--difficulty easy|medium|hard | --moves <min>-<max>]
+// Prints the tiles on stdout so they can be passed straight back to the solver, and the seed
+// on stderr so a randomly seeded board can be reproduced later. When a difficulty or move range
+// is given, the optimal solution length of the board is printed on stderr as well
+fn generate(args: &[String]) {
+    let mut size = 4;
+    let mut seed = None;
+    let mut difficulty = None;
+    let mut moves = None;
+
+    let mut index = 0;
+    while index < args.len() {
+        match (args[index].as_str(), args.get(index + 1)) {
+            ("--seed", Some(value)) => {
+                seed = Some(value.parse::<u64>().expect("Invalid --seed value"));
+                index += 2;
+            },
+            ("--difficulty", Some(value)) => {
+                difficulty = Some(parse_difficulty(value));
+                index += 2;
+            },
+            ("--moves", Some(value)) => {
+                moves = Some(parse_range(value));
+                index += 2;
+            },
+            (value, _) => {
+                size = value.parse::<usize>().expect("Invalid board size");
+                index += 1;
+            }
+        }
+    }
+
+    // Tiles are stored as u8, so 16x16 is as large as a board can get
+    if !(2..=16).contains(&size) {
+        panic!("Board size must be between 2 and 16");
+    }
+
+    let seed  = seed.unwrap_or_else(random);
+    let range = moves.or_else(|| difficulty.map(|difficulty: Difficulty| difficulty.move_range(size)));
+
+    let board = match range {
+        Some((min_moves, max_moves)) => {
+            let (board, optimal) = Board::generate_board_with_difficulty_seeded(size, min_moves, max_moves, seed)
+                .unwrap_or_else(|| panic!("Unable to find a board needing {}-{} moves within the search budget", min_moves, max_moves));
+            eprintln!("optimal moves: {}", optimal);
+            board
+        },
+        None => Board::generate_board_seeded(size, seed)
+    };
+
+    board.print_flat();
+    println!();
+    eprintln!("seed: {}", seed);
+}
+
+// analyze [size | goal tiles] [--output <table file>]
+// Breadth first searches every board that can reach the goal (the standard 3x3 goal by default),
+// printing how many boards are each distance away and the hardest boards. --output saves the
+// distance table for DistanceTable::load
+fn analyze(args: &[String]) {
+    let mut args = args.to_vec();
+    let output = take_option(&mut args, "--output");
+
+    let goal = match args.len() {
+        0 => Board::goal_board(3),
+        1 => Board::goal_board(args[0].parse::<usize>().expect("Invalid board size")),
+        _ => parse_args(&args)
+    };
+
+    let start_time = Instant::now();
+    let table = DistanceTable::build(&goal).unwrap_or_else(|err| panic!("{}", err));
+    eprintln!("{} boards analysed in {:?}", table.board_count(), start_time.elapsed());
+
+    println!("distance boards");
+    for (distance, count) in table.histogram().iter().enumerate() {
+        println!("{:>8} {}", distance, count);
+    }
+
+    println!("hardest boards ({} moves):", table.max_distance());
+    for board in table.hardest() {
+        board.print_flat();
+        println!();
+    }
+
+    if let Some(path) = output {
+        table.save(&path).unwrap_or_else(|err| panic!("{}", err));
+        eprintln!("distance table written to {}", path);
+    }
+}
 
-    if args.len() != 16 {
-        panic!("Not enough command line arguments supplied");
+// crosscheck [size] [--every <n>] [--algorithm ...] [--heuristic ...]
+// Solves every board of a small size (3x3 by default), or every nth, and checks each solution is
+// as short as the breadth first search says it can be. Defaults to IDA*
+fn crosscheck(args: &[String]) {
+    let mut args = args.to_vec();
+    let stride = take_option(&mut args, "--every").map_or(1, |value| value.parse::<usize>().expect("Invalid --every value"));
+
+    let algorithm_given = args.iter().any(|arg| arg == "--algorithm");
+    let mut config = parse_config(&mut args);
+    if !algorithm_given {
+        config.algorithm = Algorithm::IdaStar;
     }
+    let size = args.first().map_or(3, |value| value.parse::<usize>().expect("Invalid board size"));
 
-    let mut index: usize = 0;
+    let start_time = Instant::now();
+    let table = DistanceTable::build(&Board::goal_board(size)).unwrap_or_else(|err| panic!("{}", err));
+
+    match table.crosscheck(&config, stride) {
+        Ok(checked) => println!("{} boards solved optimally by {} in {:?}", checked, config.algorithm.name(), start_time.elapsed()),
+        Err(err) => {
+            println!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+// build-pdb <tiles, ex. 1,2,3,5,6> [size | goal tiles] [--output <file>] [--work-dir <dir>] [--buffer <states>]
+// Builds a pattern database for the tiles against the goal (the standard 4x4 goal by default),
+// searching on disk in the work directory (<output>.work by default). If the build is interrupted
+// running the same command again resumes it from the last finished layer
+fn build_pdb(args: &[String]) {
+    let mut args = args.to_vec();
+    let output = take_option(&mut args, "--output");
+    let work_dir = take_option(&mut args, "--work-dir");
+    let buffer_states = take_option(&mut args, "--buffer").map_or(DEFAULT_BUFFER_STATES, |value| value.parse::<usize>().expect("Invalid --buffer value"));
+
+    if args.is_empty() {
+        panic!("Usage: slider_solver build-pdb <tiles, ex. 1,2,3> [size | goal tiles] [--output <file>] [--work-dir <dir>] [--buffer <states>]");
+    }
+    let tiles: Vec<u8> = args[0].split(',').map(|tile| tile.parse::<u8>().expect("Invalid pattern tile")).collect();
+    let goal = match args.len() {
+        1 => Board::goal_board(4),
+        2 => Board::goal_board(args[1].parse::<usize>().expect("Invalid board size")),
+        _ => parse_args(&args[1..])
+    };
+
+    let output = output.unwrap_or_else(|| format!("pattern-{}.pdb", args[0].replace(',', "-")));
+    let work_dir = work_dir.unwrap_or_else(|| format!("{}.work", output));
+    build_one_pdb(&goal, &tiles, &output, &work_dir, buffer_states);
+}
 
-    for arg in args {
-        state[index] = arg.parse::<u8>().unwrap();
-        index += 1;
+// build-pdbs [--dir <dir>] [--work-dir <dir>] [--buffer <states>]
+// Builds the four 6-tile databases of the standard 5x5 partition into the directory (patterns-5x5
+// by default) for pattern-ida-star, skipping any already there. Each is 128MB and takes about ten
+// minutes, with the work files peaking at a little over 1GB
+fn build_pdbs(args: &[String]) {
+    let mut args = args.to_vec();
+    let dir = take_option(&mut args, "--dir").unwrap_or_else(|| "patterns-5x5".to_string());
+    let work_dir = take_option(&mut args, "--work-dir").unwrap_or_else(|| format!("{}/work", dir));
+    let buffer_states = take_option(&mut args, "--buffer").map_or(DEFAULT_BUFFER_STATES, |value| value.parse::<usize>().expect("Invalid --buffer value"));
+    if let Some(arg) = args.first() {
+        panic!("Unknown argument '{}'", arg);
     }
 
-    state
+    fs::create_dir_all(&dir).unwrap_or_else(|err| panic!("Unable to create {}: {}", dir, err));
+    let goal = Board::goal_board(5);
+
+    for tiles in PARTITION_5X5.iter() {
+        let name = tiles.iter().map(|tile| tile.to_string()).collect::<Vec<_>>().join("-");
+        let output = format!("{}/pattern-{}.pdb", dir, name);
+        if fs::metadata(&output).is_ok() {
+            eprintln!("{} already built", output);
+            continue;
+        }
+        build_one_pdb(&goal, tiles, &output, &work_dir, buffer_states);
+    }
+    eprintln!("solve with --algorithm pattern-ida-star --patterns {}", dir);
+}
+
+fn build_one_pdb(goal: &Board, tiles: &[u8], output: &str, work_dir: &str, buffer_states: usize) {
+    let start_time = Instant::now();
+    let database = build_pattern_database(goal, tiles, work_dir, buffer_states, |layer| {
+        let note = if layer.resumed { " (resumed)" } else { "" };
+        eprintln!("layer {:>3}: {} states{}", layer.depth, layer.states, note);
+    }).unwrap_or_else(|err| panic!("{}", err));
+    eprintln!("{} placements, up to {} moves, built in {:?}", database.entry_count(), database.max_distance(), start_time.elapsed());
+
+    database.save(output).unwrap_or_else(|err| panic!("{}", err));
+    eprintln!("pattern database written to {}", output);
+}
+
+// build-macros [size | goal tiles] [--output <table file>]
+// Builds the macro table for solving every board of the size (4x4 by default) without search,
+// and saves it for --macros (to macros-<size>x<size>.macros by default)
+fn build_macros(args: &[String]) {
+    let mut args = args.to_vec();
+    let output = take_option(&mut args, "--output");
+
+    let goal = match args.len() {
+        0 => Board::goal_board(4),
+        1 => Board::goal_board(args[0].parse::<usize>().expect("Invalid board size")),
+        _ => parse_args(&args)
+    };
+
+    let start_time = Instant::now();
+    let table = MacroTable::build(&goal).unwrap_or_else(|err| panic!("{}", err));
+    eprintln!("{} macros in {} stages built in {:?}, solutions are at most {} moves", table.macro_count(), table.stage_count(), start_time.elapsed(), table.max_solution_length());
+
+    let output = output.unwrap_or_else(|| format!("macros-{}x{}.macros", goal.size, goal.size));
+    table.save(&output).unwrap_or_else(|err| panic!("{}", err));
+    eprintln!("macro table written to {}", output);
+}
+
+fn parse_difficulty(value: &str) -> Difficulty {
+    match value {
+        "easy"   => Difficulty::Easy,
+        "medium" => Difficulty::Medium,
+        "hard"   => Difficulty::Hard,
+        _ => panic!("Unknown difficulty '{}', expected easy, medium or hard", value)
+    }
+}
+
+// <min>-<max>, or a single number for an exact length
+fn parse_range(value: &str) -> (usize, usize) {
+    let bounds: Vec<usize> = value.split('-').map(|bound| bound.parse::<usize>().expect("Invalid --moves value")).collect();
+    match bounds[..] {
+        [exact] => (exact, exact),
+        [min, max] if min <= max => (min, max),
+        _ => panic!("Invalid --moves value '{}', expected <min>-<max>", value)
+    }
+}
+
+// Removes `name <value>` from the arguments and returns the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == name)?;
+    let value = args.get(position + 1).cloned().unwrap_or_else(|| panic!("Missing value for {}", name));
+    args.drain(position..position + 2);
+    Some(value)
+}
+
+// [--algorithm bidirectional|ida-star|ida-star-stm|weighted-a-star|bfs|bidirectional-bfs|frontier-a-star
+//              |pattern-ida-star|reduction] [--heuristic manhattan|linear-conflict]
+// [--epsilon <bound on path length, as a multiple of optimal>]
+// [--max-nodes <nodes>] [--max-states <stored states>] [--time-limit <seconds>]
+// [--patterns <pattern database files or directories, comma separated>]
+fn parse_config(args: &mut Vec<String>) -> SolverConfig {
+    let mut config = SolverConfig::default();
+
+    if let Some(name) = take_option(args, "--algorithm") {
+        config.algorithm = Algorithm::from_name(&name).unwrap_or_else(|| panic!("Unknown --algorithm '{}'", name));
+    }
+    if let Some(name) = take_option(args, "--heuristic") {
+        config.heuristic = HeuristicKind::from_name(&name).unwrap_or_else(|| panic!("Unknown --heuristic '{}'", name));
+    }
+    if let Some(value) = take_option(args, "--epsilon") {
+        config.epsilon = value.parse::<f64>().expect("Invalid --epsilon value");
+    }
+    if let Some(value) = take_option(args, "--max-nodes") {
+        config.limits.max_nodes = Some(value.parse::<usize>().expect("Invalid --max-nodes value"));
+    }
+    if let Some(value) = take_option(args, "--max-states") {
+        config.limits.max_stored_states = Some(value.parse::<usize>().expect("Invalid --max-states value"));
+    }
+    if let Some(value) = take_option(args, "--time-limit") {
+        config.limits.time_limit = Some(Duration::from_secs_f64(value.parse::<f64>().expect("Invalid --time-limit value")));
+    }
+    if let Some(value) = take_option(args, "--patterns") {
+        let paths: Vec<&str> = value.split(',').collect();
+        config.patterns = Some(Arc::new(AdditivePatterns::load(&paths).unwrap_or_else(|err| panic!("{}", err))));
+    }
+    config
+}
+
+// The first Ctrl-C stops the search and prints how far it got, a second one exits straight away
+fn stop_on_ctrl_c(mut config: SolverConfig) -> SolverConfig {
+    let cancel = CancelToken::new();
+    let handler_cancel = cancel.clone();
+
+    ctrlc::set_handler(move || {
+        if handler_cancel.is_cancelled() {
+            process::exit(130);
+        }
+        handler_cancel.cancel();
+    }).expect("Unable to set the Ctrl-C handler");
+
+    config.cancel = Some(cancel);
+    config
+}
+
+// How many nodes to search between progress updates, and the least time between redrawing them
+const PROGRESS_INTERVAL: usize = 10_000;
+const PROGRESS_REDRAW: Duration = Duration::from_millis(100);
+
+fn solve_reporting(start: &Board, goal: &Board, config: &SolverConfig, show_progress: bool) -> Result<Metrics, SolveError> {
+    if !show_progress {
+        return solve(start, goal, config);
+    }
+
+    let mut last_drawn: Option<Duration> = None;
+    let result = solve_with_progress(start, goal, config, PROGRESS_INTERVAL, |progress| {
+        if last_drawn.is_some_and(|drawn| progress.running_time < drawn + PROGRESS_REDRAW) {
+            return;
+        }
+        last_drawn = Some(progress.running_time);
+        eprint!("\r{}", format_progress(progress));
+    });
+
+    if last_drawn.is_some() {
+        eprintln!();
+    }
+    result
+}
+
+// ex. "1520000 nodes, f bound 52, frontier 81234 / 79012, 310466 stored, 1.2s"
+fn format_progress(progress: &Progress) -> String {
+    let f_bound = progress.f_bound.map_or("-".to_string(), |bound| bound.to_string());
+    let frontier: Vec<String> = progress.frontier_sizes.iter().map(|size| size.to_string()).collect();
+
+    format!("{} nodes, f bound {}, frontier {}, {} stored, {:.1}s", progress.nodes_expanded, f_bound, frontier.join(" / "),
+            progress.stored_states, progress.running_time.as_secs_f64())
+}
+
+fn anytime_solve(start: &Board, goal: &Board, config: &SolverConfig) -> Vec<Move> {
+    let result = anytime_solver(start, goal, config, |improvement| {
+        eprintln!("{} moves (optimal is at least {}), {:?}, {} nodes", improvement.cost(), improvement.lower_bound, improvement.running_time, improvement.nodes_expanded);
+        true
+    });
+
+    match result {
+        Ok(Some(best)) => {
+            eprintln!("best {} moves, optimal is at least {}", best.cost(), best.lower_bound);
+            best.path
+        },
+        Ok(None) => panic!("No solution found before the search stopped"),
+        Err(err) => panic!("{}", err)
+    }
 }
 
 fn main() {
@@ -114,26 +491,71 @@ fn main() {
 
     let mut args: Vec<String> = env::args().collect();
     args.remove(0);
-    let state = parse_args(&args);
-    let end : [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
-
 
-    let start = Board {
-        size: 4,
-        state: state,
-        m_list: Vec::new(),
-        cost: 0,
-        score: 0,
-    };
+    match args.first().map(String::as_str) {
+        Some("regress") => {
+            regress(&args[1..]);
+            return;
+        },
+        Some("generate") => {
+            generate(&args[1..]);
+            return;
+        },
+        Some("analyze") => {
+            analyze(&args[1..]);
+            return;
+        },
+        Some("crosscheck") => {
+            crosscheck(&args[1..]);
+            return;
+        },
+        Some("build-pdb") => {
+            build_pdb(&args[1..]);
+            return;
+        },
+        Some("build-pdbs") => {
+            build_pdbs(&args[1..]);
+            return;
+        },
+        Some("build-macros") => {
+            build_macros(&args[1..]);
+            return;
+        },
+        _ => ()
+    }
 
+    // --optimize shortens the solution afterwards and reports by how much
+    let optimize = args.iter().any(|arg| arg == "--optimize");
+    args.retain(|arg| arg != "--optimize");
+
+    // --progress keeps a line on stderr updated with how the search is going
+    let progress = args.iter().any(|arg| arg == "--progress");
+    args.retain(|arg| arg != "--progress");
+
+    // --anytime <seconds> keeps improving the solution for up to that long, reporting each one
+    let anytime = take_option(&mut args, "--anytime")
+        .map(|value| Duration::from_secs_f64(value.parse::<f64>().expect("Invalid --anytime value")));
+
+    // --table <file> looks the solution up in a distance table saved by 'analyze --output'
+    let table = take_option(&mut args, "--table")
+        .map(|path| DistanceTable::load(&path).unwrap_or_else(|err| panic!("{}", err)));
+
+    // --macros <file> replays a macro table saved by 'build-macros'
+    let macros = take_option(&mut args, "--macros")
+        .map(|path| MacroTable::load(&path).unwrap_or_else(|err| panic!("{}", err)));
+
+    // The anytime search starts greedier than the others unless told otherwise
+    let epsilon_given = args.iter().any(|arg| arg == "--epsilon");
+    let mut config = parse_config(&mut args);
+    if let Some(time_budget) = anytime {
+        config.limits.time_limit = Some(config.limits.time_limit.map_or(time_budget, |limit| limit.min(time_budget)));
+        if !epsilon_given {
+            config.epsilon = DEFAULT_ANYTIME_WEIGHT;
+        }
+    }
 
-    let goal = Board {
-        size: 4,
-        state: end,
-        m_list: Vec::new(),
-        cost: 0,
-        score: 0
-    };
+    let start = parse_args(&args);
+    let goal  = Board::goal_board(start.size);
 
     //start.print();
     //println!();
@@ -145,7 +567,31 @@ fn main() {
 
     let start_time = Instant::now();
 
-    let solution = bidirectional_solver(&start, &goal);
+    let mut solution = match (table, macros, anytime) {
+        (Some(table), _, _) => lookup_solver(&table, &start).unwrap_or_else(|err| panic!("{}", err)),
+        (None, Some(macros), _) => macro_solver(&macros, &start).unwrap_or_else(|err| panic!("{}", err)),
+        (None, None, Some(_)) => anytime_solve(&start, &goal, &stop_on_ctrl_c(config)),
+        (None, None, None) => match solve_reporting(&start, &goal, &stop_on_ctrl_c(config), progress) {
+            Ok(metrics) => metrics.path,
+            // Print the closest the search got, flagged on stderr so stdout keeps the usual format
+            Err(SolveError::BudgetExhausted(limit, partial)) => {
+                eprintln!("stopped at the {} limit after {} nodes, partial path of {} moves", limit, partial.nodes_expanded, partial.path.len());
+                partial.path
+            },
+            Err(SolveError::Cancelled(partial)) => {
+                eprintln!("cancelled after {} nodes, partial path of {} moves", partial.nodes_expanded, partial.path.len());
+                partial.path
+            },
+            Err(err) => panic!("{}", err)
+        }
+    };
+
+    if optimize {
+        let optimized = optimize::optimize_path(&start, &solution).unwrap_or_else(|err| panic!("{}", err));
+        // Report on stderr so stdout keeps the usual one line format
+        eprintln!("optimized {}", optimized.summary());
+        solution = optimized.path;
+    }
 
     let duration = start_time.elapsed();
     start.print_flat();
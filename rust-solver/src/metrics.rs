@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use super::Move;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metrics {
+    // The moves (of the blank square) that take the start board to the goal board
+    pub path: Vec<Move>,
+
+    // Number of moves in the path
+    pub cost_of_path: usize,
+
+    // Count of child boards generated while searching
+    pub nodes_expanded: usize,
+
+    // Wall clock time spent inside the solver
+    pub running_time: Duration
+}
+
+impl Metrics {
+    pub fn display(&self) {
+        println!("path_to_goal: {:?}", self.path);
+        println!("cost_of_path: {}", self.cost_of_path);
+        println!("nodes_expanded: {}", self.nodes_expanded);
+        println!("running_time: {:?}", self.running_time);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use super::{Board, Move};
+use super::heuristics::HeuristicKind;
+use super::ida_star::IdaStar;
+use super::search::{Search, SearchStatus};
+
+// Default length of the stretches of path that get re-solved optimally, and the most nodes
+// spent re-solving each one. Windows that don't finish within the budget are left alone
+pub const DEFAULT_WINDOW: usize = 20;
+pub const DEFAULT_WINDOW_BUDGET: usize = 200_000;
+
+pub struct OptimizedPath {
+    pub path: Vec<Move>,
+    pub original_length: usize,
+
+    // Moves removed by each pass, in the order they run
+    pub inverse_pairs_removed: usize,
+    pub cycles_removed: usize,
+    pub windows_removed: usize
+}
+
+impl OptimizedPath {
+    pub fn moves_saved(&self) -> usize {
+        self.original_length - self.path.len()
+    }
+
+    // ex. "142 -> 116 moves (0 inverse pairs, 0 in cycles, 26 from re-solving)"
+    pub fn summary(&self) -> String {
+        format!("{} -> {} moves ({} inverse pairs, {} in cycles, {} from re-solving)", self.original_length, self.path.len(),
+                self.inverse_pairs_removed, self.cycles_removed, self.windows_removed)
+    }
+}
+
+// Shortens a path from start_board without changing where it ends up. Useful on the weighted
+// solvers' paths, which often wander. Fails if the board isn't valid or the path leaves it
+pub fn optimize_path(start_board: &Board, path: &[Move]) -> Result<OptimizedPath, String> {
+    optimize_path_with(start_board, path, DEFAULT_WINDOW, DEFAULT_WINDOW_BUDGET)
+}
+
+pub fn optimize_path_with(start_board: &Board, path: &[Move], window: usize, budget_nodes: usize) -> Result<OptimizedPath, String> {
+    start_board.validate()?;
+    if start_board.apply_moves(path).is_none() {
+        return Err("the path leaves the board".to_string());
+    }
+
+    let without_pairs = cancel_inverse_pairs(path);
+    let without_cycles = splice_cycles(start_board, &without_pairs);
+    let resolved = resolve_windows(start_board, &without_cycles, window, budget_nodes);
+
+    Ok(OptimizedPath {
+        original_length: path.len(),
+        inverse_pairs_removed: path.len() - without_pairs.len(),
+        cycles_removed: without_pairs.len() - without_cycles.len(),
+        windows_removed: without_cycles.len() - resolved.len(),
+        path: resolved
+    })
+}
+
+// Drops moves that are immediately undone, ex. Up Left Right Down -> nothing
+fn cancel_inverse_pairs(path: &[Move]) -> Vec<Move> {
+    let mut result: Vec<Move> = Vec::with_capacity(path.len());
+    for the_move in path {
+        if result.last() == Some(&the_move.inverse()) {
+            result.pop();
+        }
+        else if *the_move != Move::None {
+            result.push(*the_move);
+        }
+    }
+    result
+}
+
+// Whenever the path comes back to a board it has already been on, cut out everything in between
+fn splice_cycles(start_board: &Board, path: &[Move]) -> Vec<Move> {
+    let mut result: Vec<Move> = Vec::with_capacity(path.len());
+
+    // Boards on the path so far, and how many moves in each one was reached
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut board = Board::new(start_board.size, start_board.state.clone());
+    seen.insert(board.state.clone(), 0);
+
+    for the_move in path {
+        let next = board.apply_move(*the_move).expect("checked paths stay on the board");
+        board = Board::new(next.size, next.state);
+
+        match seen.get(&board.state) {
+            Some(length) => {
+                let length = *length;
+                result.truncate(length);
+                seen.retain(|_, index| *index <= length);
+            },
+            None => {
+                result.push(*the_move);
+                seen.insert(board.state.clone(), result.len());
+            }
+        }
+    }
+
+    result
+}
+
+// Re-solves overlapping stretches of the path optimally, replacing any that can be done in fewer
+// moves. Repeats until a whole pass finds nothing shorter
+fn resolve_windows(start_board: &Board, path: &[Move], window: usize, budget_nodes: usize) -> Vec<Move> {
+    let mut path = path.to_vec();
+    if window < 2 {
+        return path;
+    }
+
+    loop {
+        let mut improved = false;
+        let mut board = Board::new(start_board.size, start_board.state.clone());
+        let mut begin = 0;
+
+        while begin < path.len() {
+            let end = (begin + window).min(path.len());
+            let window_end = board.apply_moves(&path[begin..end]).expect("checked paths stay on the board");
+
+            let mut search = IdaStar::new(&board, &window_end, HeuristicKind::LinearConflict);
+            if let SearchStatus::Solved(shorter) = search.step(budget_nodes) {
+                if shorter.len() < end - begin {
+                    path.splice(begin..end, shorter);
+                    improved = true;
+                }
+            }
+
+            // Overlap windows by half so shortcuts across a window boundary are found too
+            let advance = (window / 2).min(path.len() - begin);
+            let next = board.apply_moves(&path[begin..begin + advance]).expect("checked paths stay on the board");
+            board = Board::new(next.size, next.state);
+            begin += advance;
+        }
+
+        if !improved {
+            return path;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // A wandering path of random legal moves, full of moves that get undone and loops
+    fn random_path(start_board: &Board, length: usize, seed: u64) -> Vec<Move> {
+        let moves = [Move::Up, Move::Down, Move::Left, Move::Right];
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut board = Board::new(start_board.size, start_board.state.clone());
+        let mut path = Vec::new();
+
+        while path.len() < length {
+            let the_move = moves[rng.gen_range(0..moves.len())];
+            if let Some(next) = board.apply_move(the_move) {
+                board = Board::new(next.size, next.state);
+                path.push(the_move);
+            }
+        }
+        path
+    }
+
+    #[test]
+    fn optimized_paths_are_no_longer_and_end_in_the_same_place() {
+        for (size, count) in [(3, 20), (4, 3)].iter() {
+            for seed in 0..*count {
+                let start = Board::generate_board_seeded(*size, seed);
+                let path = random_path(&start, 80, seed);
+                let end = start.apply_moves(&path).unwrap();
+
+                let optimized = optimize_path(&start, &path).unwrap();
+                assert!(optimized.path.len() <= path.len());
+                assert_eq!(optimized.original_length, path.len());
+                assert_eq!(optimized.moves_saved(), path.len() - optimized.path.len());
+                assert_eq!(start.apply_moves(&optimized.path).unwrap().state, end.state);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_that_are_undone_are_removed() {
+        let goal = Board::goal_board(4);
+        let optimized = optimize_path(&goal, &[Move::Up, Move::Left, Move::Right, Move::Down, Move::Up]).unwrap();
+
+        assert_eq!(optimized.path, vec![Move::Up]);
+        assert_eq!(optimized.inverse_pairs_removed, 4);
+        assert_eq!(optimized.cycles_removed, 0);
+        assert_eq!(optimized.windows_removed, 0);
+    }
+
+    #[test]
+    fn loops_back_to_the_same_board_are_removed() {
+        // Taking the blank round a 2x2 square turns three tiles, three times round puts them back
+        let goal = Board::goal_board(4);
+        let mut path = [Move::Up, Move::Left, Move::Down, Move::Right].repeat(3);
+        path.push(Move::Up);
+
+        let optimized = optimize_path(&goal, &path).unwrap();
+        assert_eq!(optimized.path, vec![Move::Up]);
+        assert_eq!(optimized.inverse_pairs_removed, 0);
+        assert_eq!(optimized.cycles_removed, 12);
+        assert_eq!(optimized.windows_removed, 0);
+    }
+
+    #[test]
+    fn detours_are_re_solved() {
+        // Once round a 2x2 square and the blank is back, but three tiles have turned. Going round
+        // the other way twice is the same board in 8 moves rather than 4
+        let goal = Board::goal_board(4);
+        let path = [Move::Left, Move::Up, Move::Right, Move::Down].repeat(2);
+        let once_round = [Move::Up, Move::Left, Move::Down, Move::Right];
+        assert!(goal.apply_moves(&path).unwrap() == goal.apply_moves(&once_round).unwrap());
+
+        let optimized = optimize_path(&goal, &path).unwrap();
+        assert_eq!(optimized.path.len(), 4);
+        assert_eq!(optimized.windows_removed, 4);
+    }
+
+    #[test]
+    fn paths_that_leave_the_board_are_rejected() {
+        let goal = Board::goal_board(4);
+        assert!(optimize_path(&goal, &[Move::Down]).is_err());
+        assert!(optimize_path(&goal, &[Move::Up, Move::Down, Move::Down]).is_err());
+        assert!(optimize_path(&Board::new(4, vec![0; 16]), &[]).is_err());
+    }
+}
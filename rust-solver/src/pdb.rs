@@ -0,0 +1,494 @@
+use std::fmt;
+use std::fs;
+
+use super::Board;
+use super::ranking::{partial_permutation_count, rank_partial};
+
+// Start of a saved pattern database, followed by a version byte, the board size, the number of
+// pattern tiles, the pattern tiles, the goal tiles and then one distance byte per placement
+const MAGIC: &[u8; 4] = b"SPDB";
+const VERSION: u8 = 1;
+
+// Entries for placements the search never reached, ex. the unsolvable half when the pattern is
+// every tile
+pub const UNREACHED: u8 = u8::MAX;
+
+// Marks tiles that aren't part of the pattern in PatternDatabase::slots
+const NOT_IN_PATTERN: u8 = u8::MAX;
+
+// For every placement of a few tiles (the pattern), the fewest moves of those tiles needed to get
+// them to their goal cells, with the other tiles treated as indistinguishable. Moves of the other
+// tiles are free, so databases over disjoint patterns can be added together and stay admissible.
+// Indexed by ranking::rank_partial of the pattern tiles' cells, in the order of `tiles`. Built by
+// pdb_builder::build_pattern_database
+pub struct PatternDatabase {
+    goal: Board,
+    tiles: Vec<u8>,
+
+    // Where each tile is in `tiles`, indexed by tile value, NOT_IN_PATTERN for the rest
+    slots: Vec<u8>,
+
+    distances: Vec<u8>
+}
+
+impl PatternDatabase {
+    pub(crate) fn new(goal_board: &Board, tiles: &[u8], distances: Vec<u8>) -> PatternDatabase {
+        let mut slots = vec![NOT_IN_PATTERN; goal_board.state.len()];
+        for (slot, tile) in tiles.iter().enumerate() {
+            slots[*tile as usize] = slot as u8;
+        }
+
+        PatternDatabase {
+            goal: Board::new(goal_board.size, goal_board.state.clone()),
+            tiles: tiles.to_vec(),
+            slots,
+            distances
+        }
+    }
+
+    pub fn goal(&self) -> &Board {
+        &self.goal
+    }
+
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn max_distance(&self) -> usize {
+        self.distances.iter().copied().filter(|distance| *distance != UNREACHED).max().unwrap_or(0) as usize
+    }
+
+    // Moves of the pattern tiles needed to put them in their goal cells from this board
+    pub fn estimate(&self, state: &[u8]) -> i32 {
+        let mut positions = [0u8; u8::MAX as usize];
+        for (index, tile) in state.iter().enumerate() {
+            let slot = self.slots[*tile as usize];
+            if slot != NOT_IN_PATTERN {
+                positions[slot as usize] = index as u8;
+            }
+        }
+        self.estimate_positions(&positions[..self.tiles.len()])
+    }
+
+    // Same as estimate, given the cell of each pattern tile in the order of `tiles`
+    pub fn estimate_positions(&self, positions: &[u8]) -> i32 {
+        self.distances[rank_partial(positions, self.goal.state.len()) as usize] as i32
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 3 + self.tiles.len() + self.goal.state.len() + self.distances.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.goal.size as u8);
+        bytes.push(self.tiles.len() as u8);
+        bytes.extend_from_slice(&self.tiles);
+        bytes.extend_from_slice(&self.goal.state);
+        bytes.extend_from_slice(&self.distances);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<PatternDatabase, String> {
+        if bytes.len() < MAGIC.len() + 3 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("not a pattern database".to_string());
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(format!("unsupported pattern database version {}", bytes[MAGIC.len()]));
+        }
+
+        let size = bytes[MAGIC.len() + 1] as usize;
+        let tile_count = bytes[MAGIC.len() + 2] as usize;
+        let tiles_start = MAGIC.len() + 3;
+        let goal_start = tiles_start + tile_count;
+        let goal_end = goal_start + size * size;
+        if bytes.len() < goal_end {
+            return Err("pattern database is truncated".to_string());
+        }
+
+        let goal = Board::new(size, bytes[goal_start..goal_end].to_vec());
+        goal.validate().map_err(|err| format!("invalid goal in pattern database: {}", err))?;
+        let tiles = &bytes[tiles_start..goal_start];
+        check_pattern(&goal, tiles)?;
+
+        let count = partial_permutation_count(size * size, tile_count);
+        if bytes.len() as u64 != goal_end as u64 + count {
+            return Err(format!("a {}x{} pattern database of {} tiles should be {} bytes, got {}", size, size, tile_count, goal_end as u64 + count, bytes.len()));
+        }
+
+        Ok(PatternDatabase::new(&goal, tiles, bytes[goal_end..].to_vec()))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|err| format!("unable to write {}: {}", path, err))
+    }
+
+    pub fn load(path: &str) -> Result<PatternDatabase, String> {
+        let bytes = fs::read(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+        PatternDatabase::from_bytes(&bytes).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+// Checks the tiles make a pattern for the goal: some tiles of the board, none repeated, not the blank
+pub(crate) fn check_pattern(goal_board: &Board, tiles: &[u8]) -> Result<(), String> {
+    let cells = goal_board.state.len();
+    if tiles.is_empty() {
+        return Err("a pattern needs at least one tile".to_string());
+    }
+
+    let mut seen = vec![false; cells];
+    for tile in tiles {
+        if *tile == 0 || *tile as usize >= cells {
+            return Err(format!("{} isn't a tile of a {}x{} board", tile, goal_board.size, goal_board.size));
+        }
+        if seen[*tile as usize] {
+            return Err(format!("tile {} is in the pattern twice", tile));
+        }
+        seen[*tile as usize] = true;
+    }
+    Ok(())
+}
+
+// The standard 5x5 partition into four 6-tile patterns, each a compact block of the goal so the
+// tiles interact as much as possible within a pattern and as little as possible between them
+pub const PARTITION_5X5: [[u8; 6]; 4] = [
+    [1, 2, 3, 6, 7, 8],
+    [4, 5, 9, 10, 14, 15],
+    [11, 12, 16, 17, 21, 22],
+    [13, 18, 19, 20, 23, 24]
+];
+
+// The sum of several pattern databases over disjoint tiles, an admissible estimate of the moves
+// left. Loads the files written by the 'build-pdb' command.
+//
+// When the goal's blank is on the main diagonal, reflecting a board in that diagonal (and
+// renaming each tile after the one in the mirrored goal cell) gives a board exactly as far from
+// the goal. The databases are looked up for the reflection too and the larger sum used, which is
+// still admissible and often catches what the partition misses
+pub struct AdditivePatterns {
+    databases: Vec<PatternDatabase>,
+    size: usize,
+
+    // For each tile, its index into the combined positions of every pattern's tiles, NOT_IN_PATTERN
+    // if it's in none of them
+    lookup: Vec<u8>,
+
+    // Which database each of the combined positions belongs to, and where each database's start
+    database_of: Vec<usize>,
+    offsets: Vec<usize>,
+
+    // For each tile, its name in the reflected goal, empty if the goal isn't symmetric
+    mirror: Vec<u8>
+}
+
+impl fmt::Debug for AdditivePatterns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tiles: Vec<_> = self.databases.iter().map(|database| &database.tiles).collect();
+        write!(f, "AdditivePatterns {{ goal: {:?}, tiles: {:?}, reflected: {} }}", self.goal().state, tiles, self.reflected())
+    }
+}
+
+impl AdditivePatterns {
+    // The databases have to share a goal and no tile can be in more than one of them
+    pub fn new(databases: Vec<PatternDatabase>) -> Result<AdditivePatterns, String> {
+        let first = databases.first().ok_or("no pattern databases given")?;
+        let (size, cells) = (first.goal.size, first.goal.state.len());
+        let mut lookup = vec![NOT_IN_PATTERN; cells];
+        let mut database_of = Vec::new();
+        let mut offsets = Vec::new();
+
+        for (index, database) in databases.iter().enumerate() {
+            if database.goal != first.goal {
+                return Err(format!("pattern databases are for different goals, {:?} and {:?}", first.goal.state, database.goal.state));
+            }
+            offsets.push(database_of.len());
+            for tile in &database.tiles {
+                if lookup[*tile as usize] != NOT_IN_PATTERN {
+                    return Err(format!("tile {} is in more than one pattern database", tile));
+                }
+                lookup[*tile as usize] = database_of.len() as u8;
+                database_of.push(index);
+            }
+        }
+
+        let goal = &first.goal.state;
+        let blank = first.goal.get_index_of(&0);
+        let mirror = if blank / size == blank % size {
+            let mut mirror = vec![0; cells];
+            for (cell, tile) in goal.iter().enumerate() {
+                mirror[*tile as usize] = goal[transpose(cell, size)];
+            }
+            mirror
+        }
+        else {
+            Vec::new()
+        };
+
+        Ok(AdditivePatterns { databases, size, lookup, database_of, offsets, mirror })
+    }
+
+    // Each path is a pattern database file, or a directory whose .pdb files are all loaded
+    pub fn load(paths: &[&str]) -> Result<AdditivePatterns, String> {
+        let mut files = Vec::new();
+        for path in paths {
+            match fs::read_dir(path) {
+                Ok(entries) => {
+                    let mut found: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .filter(|file| file.extension().is_some_and(|extension| extension == "pdb"))
+                        .map(|file| file.to_string_lossy().into_owned())
+                        .collect();
+                    if found.is_empty() {
+                        return Err(format!("no .pdb files in {}", path));
+                    }
+                    found.sort();
+                    files.extend(found);
+                },
+                Err(_) => files.push(path.to_string())
+            }
+        }
+
+        let databases = files.iter().map(|file| PatternDatabase::load(file)).collect::<Result<Vec<_>, _>>()?;
+        AdditivePatterns::new(databases)
+    }
+
+    pub fn goal(&self) -> &Board {
+        &self.databases[0].goal
+    }
+
+    pub fn databases(&self) -> &[PatternDatabase] {
+        &self.databases
+    }
+
+    // Whether boards are looked up reflected as well
+    pub fn reflected(&self) -> bool {
+        !self.mirror.is_empty()
+    }
+
+    pub fn estimate(&self, state: &[u8]) -> i32 {
+        self.track(state).estimate(self)
+    }
+
+    // Starts following a board, see PatternTracker
+    pub fn track(&self, state: &[u8]) -> PatternTracker {
+        let mut positions = vec![0; self.database_of.len()];
+        let mut reflected_positions = vec![0; self.database_of.len()];
+
+        for (cell, tile) in state.iter().enumerate() {
+            let index = self.lookup[*tile as usize];
+            if index != NOT_IN_PATTERN {
+                positions[index as usize] = cell as u8;
+            }
+
+            // The reflection has the mirrored tile in the mirrored cell
+            if self.reflected() {
+                let index = self.lookup[self.mirror[*tile as usize] as usize];
+                if index != NOT_IN_PATTERN {
+                    reflected_positions[index as usize] = transpose(cell, self.size) as u8;
+                }
+            }
+        }
+
+        let count = self.databases.len();
+        let parts = (0..count).map(|database| self.lookup_database(database, &positions)).collect();
+        let reflected_parts = if self.reflected() {
+            (0..count).map(|database| self.lookup_database(database, &reflected_positions)).collect()
+        }
+        else {
+            Vec::new()
+        };
+
+        PatternTracker { positions, parts, reflected_positions, reflected_parts }
+    }
+
+    fn lookup_database(&self, database: usize, positions: &[u8]) -> i32 {
+        let start = self.offsets[database];
+        self.databases[database].estimate_positions(&positions[start..start + self.databases[database].tiles.len()])
+    }
+}
+
+// AdditivePatterns' estimate for a board that changes a slide at a time, as in a depth first
+// search. A slide moves one tile, so only the database with that tile (and the one with its
+// mirror) is looked up again, rather than all of them twice. Pass it the AdditivePatterns that
+// made it
+pub struct PatternTracker {
+    // Cell of every pattern tile, and the estimate from each database, for the board and for
+    // its reflection
+    positions: Vec<u8>,
+    parts: Vec<i32>,
+    reflected_positions: Vec<u8>,
+    reflected_parts: Vec<i32>
+}
+
+impl PatternTracker {
+    pub fn estimate(&self, patterns: &AdditivePatterns) -> i32 {
+        let direct = self.parts.iter().sum();
+        if patterns.reflected() { self.reflected_parts.iter().sum::<i32>().max(direct) } else { direct }
+    }
+
+    // The estimate after `tile` slides into the cell `to`
+    pub fn slide(&mut self, patterns: &AdditivePatterns, tile: u8, to: usize) -> i32 {
+        let index = patterns.lookup[tile as usize];
+        if index != NOT_IN_PATTERN {
+            let database = patterns.database_of[index as usize];
+            self.positions[index as usize] = to as u8;
+            self.parts[database] = patterns.lookup_database(database, &self.positions);
+        }
+
+        if patterns.reflected() {
+            let index = patterns.lookup[patterns.mirror[tile as usize] as usize];
+            if index != NOT_IN_PATTERN {
+                let database = patterns.database_of[index as usize];
+                self.reflected_positions[index as usize] = transpose(to, patterns.size) as u8;
+                self.reflected_parts[database] = patterns.lookup_database(database, &self.reflected_positions);
+            }
+        }
+
+        self.estimate(patterns)
+    }
+}
+
+fn transpose(cell: usize, size: usize) -> usize {
+    (cell % size) * size + cell / size
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{Move, Search, SearchStatus};
+    use crate::analysis::DistanceTable;
+    use crate::heuristics::HeuristicKind;
+    use crate::ida_star::IdaStar;
+    use crate::pdb_builder::build_pattern_database;
+
+    const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+    // Tests run in parallel, so every build gets a work directory of its own
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    fn build(goal: &Board, tiles: &[u8]) -> PatternDatabase {
+        let build = BUILDS.fetch_add(1, Ordering::Relaxed);
+        let work = std::env::temp_dir().join(format!("slider-pdb-test-{}-{}", std::process::id(), build));
+        build_pattern_database(goal, tiles, &work.to_string_lossy(), 1 << 16, |_| ()).unwrap()
+    }
+
+    fn partition(goal: &Board, patterns: &[&[u8]]) -> AdditivePatterns {
+        AdditivePatterns::new(patterns.iter().map(|tiles| build(goal, tiles)).collect()).unwrap()
+    }
+
+    // Three tile blocks of the 4x4 goal, small enough to build in a moment
+    const PARTITION_4X4: [&[u8]; 5] = [&[1, 2, 5], &[3, 4, 8], &[6, 7, 11], &[9, 10, 13], &[12, 14, 15]];
+
+    // 4x4 boards a random walk from the goal, near enough for IDA* to solve quickly
+    fn walked_boards(goal: &Board, count: usize, length: usize) -> Vec<Board> {
+        let mut rng = ChaCha8Rng::seed_from_u64(48);
+        (0..count).map(|_| {
+            let mut board = Board::new(goal.size, goal.state.clone());
+            for _ in 0..length {
+                if let Some(next) = board.apply_move(MOVES[rng.gen_range(0..MOVES.len())]) {
+                    board = next;
+                }
+            }
+            board
+        }).collect()
+    }
+
+    #[test]
+    fn saved_databases_load_back_the_same() {
+        let goal = Board::goal_board(3);
+        let database = build(&goal, &[2, 3, 5]);
+        let bytes = database.to_bytes();
+
+        let loaded = PatternDatabase::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert_eq!(loaded.tiles(), &[2, 3, 5]);
+        assert_eq!(loaded.goal().state, goal.state);
+        assert_eq!(loaded.max_distance(), database.max_distance());
+    }
+
+    #[test]
+    fn corrupt_databases_are_rejected() {
+        let bytes = build(&Board::goal_board(3), &[1, 4, 7]).to_bytes();
+        let goal_end = MAGIC.len() + 3 + 3 + 9;
+
+        assert!(PatternDatabase::from_bytes(b"SPDT").is_err());
+        assert!(PatternDatabase::from_bytes(&bytes[..goal_end - 1]).err().unwrap().contains("truncated"));
+        assert!(PatternDatabase::from_bytes(&bytes[..bytes.len() - 1]).err().unwrap().contains("should be"));
+
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(PatternDatabase::from_bytes(&longer).err().unwrap().contains("should be"));
+
+        let mut repeated = bytes.clone();
+        repeated[MAGIC.len() + 4] = 1;
+        assert!(PatternDatabase::from_bytes(&repeated).is_err());
+    }
+
+    #[test]
+    fn partitions_must_be_disjoint_and_share_a_goal() {
+        let goal = Board::goal_board(3);
+        let other_goal = Board::new(3, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        let database = |goal: &Board, tiles: &[u8]| PatternDatabase::new(goal, tiles, vec![0; partial_permutation_count(9, tiles.len()) as usize]);
+
+        assert!(AdditivePatterns::new(Vec::new()).is_err());
+        assert!(AdditivePatterns::new(vec![database(&goal, &[1, 2]), database(&goal, &[2, 3])]).err().unwrap().contains("more than one"));
+        assert!(AdditivePatterns::new(vec![database(&goal, &[1, 2]), database(&other_goal, &[3, 4])]).err().unwrap().contains("different goals"));
+        assert!(AdditivePatterns::new(vec![database(&goal, &[1, 2]), database(&goal, &[3, 4])]).is_ok());
+    }
+
+    #[test]
+    fn reflected_estimates_never_overestimate() {
+        // Every 3x3 board against its exact distance
+        let goal = Board::goal_board(3);
+        let patterns = partition(&goal, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        assert!(patterns.reflected());
+
+        let table = DistanceTable::build(&goal).unwrap();
+        for index in 0..table.board_count() {
+            let board = Board::new(3, crate::ranking::unrank_solvable(index as u128, 3));
+            assert!(patterns.estimate(&board.state) as usize <= table.distance(&board).unwrap(), "{:?}", board.state);
+        }
+
+        // And 4x4 boards against IDA*'s optimal lengths
+        let goal = Board::goal_board(4);
+        let patterns = partition(&goal, &PARTITION_4X4);
+        assert!(patterns.reflected());
+
+        for board in walked_boards(&goal, 20, 60) {
+            let optimal = match IdaStar::new(&board, &goal, HeuristicKind::LinearConflict).step(usize::MAX) {
+                SearchStatus::Solved(path) => path.len(),
+                _ => unreachable!("solvable boards are solved")
+            };
+            assert!(patterns.estimate(&board.state) as usize <= optimal, "{:?}", board.state);
+        }
+    }
+
+    #[test]
+    fn trackers_follow_slides() {
+        let goal = Board::goal_board(4);
+        let patterns = partition(&goal, &PARTITION_4X4);
+        let mut rng = ChaCha8Rng::seed_from_u64(480);
+
+        let mut board = Board::generate_board_seeded(4, 48);
+        let mut tracker = patterns.track(&board.state);
+        for _ in 0..500 {
+            let next = match board.apply_move(MOVES[rng.gen_range(0..MOVES.len())]) {
+                Some(next) => next,
+                None => continue
+            };
+
+            // The tile slides into the cell the blank left
+            let (from, to) = (next.get_index_of(&0), board.get_index_of(&0));
+            let estimate = tracker.slide(&patterns, board.state[from], to);
+            assert_eq!(estimate, patterns.track(&next.state).estimate(&patterns));
+            assert_eq!(estimate, patterns.estimate(&next.state));
+            board = next;
+        }
+    }
+}
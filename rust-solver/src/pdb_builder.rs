@@ -0,0 +1,588 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::Board;
+use super::pdb::{PatternDatabase, UNREACHED, check_pattern};
+use super::ranking::{partial_permutation_count, rank_partial, unrank_partial};
+
+// States held in memory at once while generating a layer, 8 bytes each. Once full they're sorted,
+// duplicates dropped and written out as a run file
+pub const DEFAULT_BUFFER_STATES: usize = 1 << 24;
+
+// Sets of cells are kept as bitboards, which limits the board to 11x11
+pub const MAX_PATTERN_CELLS: usize = 128;
+
+const MANIFEST: &str = "manifest";
+
+// Where a build stands, what `on_layer` is told as each layer is finished
+#[derive(Clone, Debug)]
+pub struct LayerReport {
+    pub depth: usize,
+    pub states: u64,
+
+    // Layers were already on disk from an earlier, interrupted build
+    pub resumed: bool
+}
+
+// Builds a pattern database by breadth first search back from the goal, keeping the search on disk
+// so it is limited by disk space rather than memory. Only the layer being generated is held in
+// memory, `buffer_states` at a time.
+//
+// A search state is where the pattern tiles are plus which region of the other cells the blank is
+// in. The blank moving within its region only moves tiles outside the pattern, which is free, so
+// the region stands for all those positions and every move left in the search costs one.
+//
+// Each layer is generated by streaming the previous one, the successors are sorted in memory-sized
+// runs, then the runs are merged dropping duplicates and anything in the previous two layers. The
+// layers are written to `work_dir` along with a manifest of how far the build got. Running it
+// again with the same work directory and pattern picks up after the last finished layer, a work
+// directory left by a different pattern is an error. Work files are removed once the database is
+// built, the database itself is held in memory at a byte per placement of the pattern tiles
+pub fn build_pattern_database<F>(goal_board: &Board, tiles: &[u8], work_dir: &str, buffer_states: usize, mut on_layer: F) -> Result<PatternDatabase, String>
+    where F: FnMut(&LayerReport)
+{
+    goal_board.validate()?;
+    check_pattern(goal_board, tiles)?;
+    let space = StateSpace::new(goal_board, tiles)?;
+
+    let work = WorkDir::open(work_dir)?;
+    let manifest = Manifest { goal: goal_board.state.clone(), tiles: tiles.to_vec(), layers: 0, complete: false };
+    let mut manifest = work.resume(manifest)?;
+
+    for depth in 0..manifest.layers {
+        on_layer(&LayerReport { depth, states: work.layer_states(depth)?, resumed: true });
+    }
+
+    if manifest.layers == 0 {
+        let mut writer = work.create(&work.layer_tmp(0))?;
+        write_state(&mut writer, space.goal_state())?;
+        work.finish(writer, &work.layer_tmp(0), &work.layer(0))?;
+
+        manifest.layers = 1;
+        work.write_manifest(&manifest)?;
+        on_layer(&LayerReport { depth: 0, states: 1, resumed: false });
+    }
+
+    while !manifest.complete {
+        let depth = manifest.layers;
+        let states = work.next_layer(&space, depth, buffer_states.max(1))?;
+
+        if states == 0 {
+            fs::remove_file(work.layer_tmp(depth)).map_err(|err| work.error(err))?;
+            manifest.complete = true;
+        }
+        else {
+            if depth >= UNREACHED as usize {
+                return Err(format!("the pattern needs more than {} moves, distances are stored in a byte", UNREACHED - 1));
+            }
+            fs::rename(work.layer_tmp(depth), work.layer(depth)).map_err(|err| work.error(err))?;
+            manifest.layers += 1;
+            on_layer(&LayerReport { depth, states, resumed: false });
+        }
+        work.write_manifest(&manifest)?;
+    }
+
+    let distances = work.collect_distances(&space, manifest.layers)?;
+    work.clean(manifest.layers)?;
+    Ok(PatternDatabase::new(goal_board, tiles, distances))
+}
+
+// Numbers search states as u64 codes: the pattern tiles' placement rank times the number of cells,
+// plus the smallest cell in the blank's region. Sets of cells are u128 bitboards, bit n for cell n
+struct StateSpace {
+    size: usize,
+    cells: usize,
+    tile_count: usize,
+    goal_placement: Vec<u8>,
+    goal_blank: usize,
+
+    // Every cell of the board, and the cells next to each cell
+    board_mask: u128,
+    neighbours: Vec<u128>,
+
+    // Cells not in the first or last column, where a shift left or right by one can land
+    not_first_column: u128,
+    not_last_column: u128
+}
+
+impl StateSpace {
+    fn new(goal_board: &Board, tiles: &[u8]) -> Result<StateSpace, String> {
+        let (size, cells) = (goal_board.size, goal_board.state.len());
+        if cells > MAX_PATTERN_CELLS {
+            return Err(format!("pattern databases can only be built for boards of up to {} cells", MAX_PATTERN_CELLS));
+        }
+
+        let placements = partial_permutation_count(cells, tiles.len());
+        if placements.checked_mul(cells as u64).is_none() || placements > usize::MAX as u64 {
+            return Err(format!("a pattern of {} tiles on a {}x{} board has too many placements to build", tiles.len(), size, size));
+        }
+
+        let column = |col: usize| (0..size).fold(0u128, |mask, row| mask | 1 << (row * size + col));
+        let board_mask = if cells == 128 { u128::MAX } else { (1u128 << cells) - 1 };
+        let mut space = StateSpace {
+            size,
+            cells,
+            tile_count: tiles.len(),
+            goal_placement: tiles.iter().map(|tile| goal_board.get_index_of(tile) as u8).collect(),
+            goal_blank: goal_board.get_index_of(&0),
+            board_mask,
+            neighbours: Vec::with_capacity(cells),
+            not_first_column: board_mask & !column(0),
+            not_last_column: board_mask & !column(size - 1)
+        };
+        space.neighbours = (0..cells).map(|cell| space.grow(1 << cell) & !(1 << cell)).collect();
+        Ok(space)
+    }
+
+    fn placement_count(&self) -> usize {
+        partial_permutation_count(self.cells, self.tile_count) as usize
+    }
+
+    fn goal_state(&self) -> u64 {
+        let occupied = self.goal_placement.iter().fold(0u128, |mask, cell| mask | 1 << cell);
+        self.encode(&self.goal_placement, occupied, self.goal_blank)
+    }
+
+    fn encode(&self, placement: &[u8], occupied: u128, blank: usize) -> u64 {
+        let smallest = self.region(occupied, blank).trailing_zeros();
+        rank_partial(placement, self.cells) * self.cells as u64 + smallest as u64
+    }
+
+    // The cells plus every cell next to one of them
+    fn grow(&self, cells: u128) -> u128 {
+        let size = self.size;
+        (cells | cells << size | cells >> size | (cells << 1 & self.not_first_column) | (cells >> 1 & self.not_last_column)) & self.board_mask
+    }
+
+    // Cells the blank can reach from `blank` without moving a pattern tile, the pattern tiles
+    // being in `occupied`
+    fn region(&self, occupied: u128, blank: usize) -> u128 {
+        let free = self.board_mask & !occupied;
+        let mut region = 1u128 << blank;
+        loop {
+            let grown = self.grow(region) & free;
+            if grown == region {
+                return region;
+            }
+            region = grown;
+        }
+    }
+
+    // Every state one pattern tile move away: a pattern tile next to the blank's region moves
+    // into it, leaving the blank where the tile was
+    fn successors(&self, state: u64, out: &mut Vec<u64>) {
+        let cells = self.cells as u64;
+        let mut placement = unrank_partial(state / cells, self.cells, self.tile_count);
+        let occupied = placement.iter().fold(0u128, |mask, cell| mask | 1 << cell);
+        let region = self.region(occupied, (state % cells) as usize);
+
+        for slot in 0..placement.len() {
+            let from = placement[slot] as usize;
+            let mut targets = self.neighbours[from] & region;
+
+            while targets != 0 {
+                let to = targets.trailing_zeros() as usize;
+                targets &= targets - 1;
+
+                placement[slot] = to as u8;
+                out.push(self.encode(&placement, occupied & !(1 << from) | 1 << to, from));
+            }
+            placement[slot] = from as u8;
+        }
+    }
+}
+
+// How far a build got, saved in the work directory after every layer
+struct Manifest {
+    goal: Vec<u8>,
+    tiles: Vec<u8>,
+    layers: usize,
+    complete: bool
+}
+
+impl Manifest {
+    fn to_text(&self) -> String {
+        format!("goal {}\ntiles {}\nlayers {}\ncomplete {}\n", join(&self.goal), join(&self.tiles), self.layers, self.complete)
+    }
+
+    fn from_text(text: &str) -> Result<Manifest, String> {
+        let mut manifest = Manifest { goal: Vec::new(), tiles: Vec::new(), layers: 0, complete: false };
+
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ').ok_or_else(|| format!("invalid manifest line '{}'", line))?;
+            match key {
+                "goal"     => manifest.goal = split(value)?,
+                "tiles"    => manifest.tiles = split(value)?,
+                "layers"   => manifest.layers = value.parse().map_err(|_| format!("invalid layer count '{}'", value))?,
+                "complete" => manifest.complete = value == "true",
+                _ => return Err(format!("unknown manifest entry '{}'", key))
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn join(values: &[u8]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn split(text: &str) -> Result<Vec<u8>, String> {
+    text.split(',').map(|value| value.parse().map_err(|_| format!("invalid number '{}'", value))).collect()
+}
+
+// The files of a build: layer-<depth> for finished layers, each a sorted list of little endian u64
+// state codes, and run-<n> and *.tmp files while a layer is being generated
+struct WorkDir {
+    path: PathBuf
+}
+
+impl WorkDir {
+    fn open(path: &str) -> Result<WorkDir, String> {
+        fs::create_dir_all(path).map_err(|err| format!("unable to create {}: {}", path, err))?;
+        Ok(WorkDir { path: PathBuf::from(path) })
+    }
+
+    fn error(&self, err: std::io::Error) -> String {
+        format!("{}: {}", self.path.display(), err)
+    }
+
+    fn layer(&self, depth: usize) -> PathBuf {
+        self.path.join(format!("layer-{}", depth))
+    }
+
+    fn layer_tmp(&self, depth: usize) -> PathBuf {
+        self.path.join(format!("layer-{}.tmp", depth))
+    }
+
+    fn run(&self, index: usize) -> PathBuf {
+        self.path.join(format!("run-{}", index))
+    }
+
+    // The manifest to carry on from: a matching one left by an earlier build, or the new one if
+    // there isn't one. Clears out files from a layer that was interrupted part way
+    fn resume(&self, manifest: Manifest) -> Result<Manifest, String> {
+        let existing = match fs::read_to_string(self.path.join(MANIFEST)) {
+            Ok(text) => Some(Manifest::from_text(&text)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(self.error(err))
+        };
+
+        for entry in fs::read_dir(&self.path).map_err(|err| self.error(err))? {
+            let name = entry.map_err(|err| self.error(err))?.file_name().to_string_lossy().into_owned();
+            if name.starts_with("run-") || name.ends_with(".tmp") {
+                fs::remove_file(self.path.join(&name)).map_err(|err| self.error(err))?;
+            }
+        }
+
+        match existing {
+            Some(existing) if existing.goal != manifest.goal || existing.tiles != manifest.tiles => {
+                Err(format!("{} holds a build of tiles {} for goal {}, use another directory", self.path.display(), join(&existing.tiles), join(&existing.goal)))
+            },
+            Some(existing) => Ok(existing),
+            None => {
+                self.write_manifest(&manifest)?;
+                Ok(manifest)
+            }
+        }
+    }
+
+    fn write_manifest(&self, manifest: &Manifest) -> Result<(), String> {
+        let tmp = self.path.join(format!("{}.tmp", MANIFEST));
+        fs::write(&tmp, manifest.to_text()).map_err(|err| self.error(err))?;
+        fs::rename(&tmp, self.path.join(MANIFEST)).map_err(|err| self.error(err))
+    }
+
+    fn create(&self, path: &Path) -> Result<BufWriter<File>, String> {
+        File::create(path).map(BufWriter::new).map_err(|err| self.error(err))
+    }
+
+    fn reader(&self, path: &Path) -> Result<StateReader, String> {
+        let file = File::open(path).map_err(|err| self.error(err))?;
+        Ok(StateReader { reader: BufReader::new(file) })
+    }
+
+    // Flushes a finished file to disk and moves it into place
+    fn finish(&self, writer: BufWriter<File>, from: &Path, to: &Path) -> Result<(), String> {
+        let file = writer.into_inner().map_err(|err| self.error(err.into_error()))?;
+        file.sync_all().map_err(|err| self.error(err))?;
+        fs::rename(from, to).map_err(|err| self.error(err))
+    }
+
+    fn layer_states(&self, depth: usize) -> Result<u64, String> {
+        let metadata = fs::metadata(self.layer(depth)).map_err(|err| self.error(err))?;
+        Ok(metadata.len() / 8)
+    }
+
+    // Generates the layer at `depth` into its tmp file, returning how many states it has
+    fn next_layer(&self, space: &StateSpace, depth: usize, buffer_states: usize) -> Result<u64, String> {
+        let mut runs = 0;
+        let mut buffer = Vec::with_capacity(buffer_states.min(DEFAULT_BUFFER_STATES));
+        let mut successors = Vec::new();
+
+        let mut parents = self.reader(&self.layer(depth - 1))?;
+        while let Some(state) = parents.next()? {
+            successors.clear();
+            space.successors(state, &mut successors);
+            buffer.extend_from_slice(&successors);
+
+            if buffer.len() >= buffer_states {
+                self.write_run(&mut buffer, runs)?;
+                runs += 1;
+            }
+        }
+        if !buffer.is_empty() || runs == 0 {
+            self.write_run(&mut buffer, runs)?;
+            runs += 1;
+        }
+        drop(buffer);
+
+        // Merge the runs, dropping duplicates and states already in the previous two layers
+        let mut heap = BinaryHeap::new();
+        let mut readers = Vec::with_capacity(runs);
+        for index in 0..runs {
+            let mut reader = self.reader(&self.run(index))?;
+            if let Some(state) = reader.next()? {
+                heap.push(Reverse((state, index)));
+            }
+            readers.push(reader);
+        }
+
+        let mut seen = vec![self.reader(&self.layer(depth - 1))?];
+        if depth >= 2 {
+            seen.push(self.reader(&self.layer(depth - 2))?);
+        }
+        let mut seen_heads = seen.iter_mut().map(|reader| reader.next()).collect::<Result<Vec<_>, _>>()?;
+
+        let tmp = self.layer_tmp(depth);
+        let mut writer = self.create(&tmp)?;
+        let mut last = None;
+        let mut states = 0;
+
+        while let Some(Reverse((state, index))) = heap.pop() {
+            if let Some(next) = readers[index].next()? {
+                heap.push(Reverse((next, index)));
+            }
+            if last == Some(state) {
+                continue;
+            }
+            last = Some(state);
+
+            let mut old = false;
+            for (reader, head) in seen.iter_mut().zip(seen_heads.iter_mut()) {
+                while head.is_some_and(|value| value < state) {
+                    *head = reader.next()?;
+                }
+                old |= *head == Some(state);
+            }
+            if !old {
+                write_state(&mut writer, state)?;
+                states += 1;
+            }
+        }
+
+        let file = writer.into_inner().map_err(|err| self.error(err.into_error()))?;
+        file.sync_all().map_err(|err| self.error(err))?;
+        for index in 0..runs {
+            fs::remove_file(self.run(index)).map_err(|err| self.error(err))?;
+        }
+        Ok(states)
+    }
+
+    fn write_run(&self, buffer: &mut Vec<u64>, index: usize) -> Result<(), String> {
+        buffer.sort_unstable();
+        buffer.dedup();
+
+        let mut writer = self.create(&self.run(index))?;
+        for state in buffer.iter() {
+            write_state(&mut writer, *state)?;
+        }
+        writer.flush().map_err(|err| self.error(err))?;
+        buffer.clear();
+        Ok(())
+    }
+
+    // Each placement's distance is the depth of the first layer it shows up in, with any region
+    fn collect_distances(&self, space: &StateSpace, layers: usize) -> Result<Vec<u8>, String> {
+        let mut distances = vec![UNREACHED; space.placement_count()];
+        let cells = space.cells as u64;
+
+        for depth in 0..layers {
+            let mut reader = self.reader(&self.layer(depth))?;
+            while let Some(state) = reader.next()? {
+                let entry = &mut distances[(state / cells) as usize];
+                if *entry == UNREACHED {
+                    *entry = depth as u8;
+                }
+            }
+        }
+        Ok(distances)
+    }
+
+    fn clean(&self, layers: usize) -> Result<(), String> {
+        for depth in 0..layers {
+            fs::remove_file(self.layer(depth)).map_err(|err| self.error(err))?;
+        }
+        fs::remove_file(self.path.join(MANIFEST)).map_err(|err| self.error(err))?;
+
+        // Only removes the directory if nothing else is in it
+        let _ = fs::remove_dir(&self.path);
+        Ok(())
+    }
+}
+
+fn write_state(writer: &mut BufWriter<File>, state: u64) -> Result<(), String> {
+    writer.write_all(&state.to_le_bytes()).map_err(|err| err.to_string())
+}
+
+struct StateReader {
+    reader: BufReader<File>
+}
+
+impl StateReader {
+    fn next(&mut self) -> Result<Option<u64>, String> {
+        let mut bytes = [0u8; 8];
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(u64::from_le_bytes(bytes))),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use super::*;
+
+    const MOVES: [crate::Move; 4] = [crate::Move::Up, crate::Move::Down, crate::Move::Left, crate::Move::Right];
+
+    // A fresh work directory under the system temp directory, unique to the test and process
+    fn work_dir(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("slider-pdb-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn build(goal: &Board, tiles: &[u8], work: &str, buffer_states: usize) -> PatternDatabase {
+        build_pattern_database(goal, tiles, work, buffer_states, |_| ()).unwrap()
+    }
+
+    // The same distances from a search held in memory over whole boards with the other tiles
+    // blanked out. Moving the blank onto an unnumbered cell is free, onto a pattern tile costs one
+    fn expected_distances(goal: &Board, tiles: &[u8]) -> Vec<u8> {
+        let cells = goal.state.len();
+        let masked = |state: &[u8]| state.iter().map(|tile| if tiles.contains(tile) || *tile == 0 { *tile } else { u8::MAX }).collect::<Vec<u8>>();
+
+        let mut distances = vec![UNREACHED; partial_permutation_count(cells, tiles.len()) as usize];
+        let mut seen: HashMap<Vec<u8>, u8> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back((masked(&goal.state), 0u8));
+
+        while let Some((state, distance)) = queue.pop_front() {
+            if seen.get(&state).is_some_and(|known| *known <= distance) {
+                continue;
+            }
+            seen.insert(state.clone(), distance);
+
+            let positions: Vec<u8> = tiles.iter().map(|tile| state.iter().position(|cell| cell == tile).unwrap() as u8).collect();
+            let entry = &mut distances[rank_partial(&positions, cells) as usize];
+            *entry = (*entry).min(distance);
+
+            let blank = state.iter().position(|tile| *tile == 0).unwrap();
+            for the_move in MOVES.iter() {
+                let next = match Board::new(goal.size, state.clone()).apply_move(*the_move) {
+                    Some(next) => next.state,
+                    None => continue
+                };
+                if next[blank] == u8::MAX {
+                    queue.push_front((next, distance));
+                } else {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        distances
+    }
+
+    fn all_placements(database: &PatternDatabase) -> Vec<u8> {
+        let cells = database.goal().state.len();
+        (0..database.entry_count() as u64)
+            .map(|rank| database.estimate_positions(&unrank_partial(rank, cells, database.tiles().len())) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn databases_match_an_in_memory_search() {
+        let goal = Board::goal_board(3);
+        for tiles in [vec![1, 2, 3], vec![4, 5, 7, 8], vec![1, 2, 3, 4, 5, 6, 7, 8]].iter() {
+            let work = work_dir("memory");
+            let database = build(&goal, tiles, &work, 1 << 16);
+
+            assert_eq!(all_placements(&database), expected_distances(&goal, tiles), "tiles {:?}", tiles);
+            assert!(!Path::new(&work).exists());
+        }
+    }
+
+    #[test]
+    fn small_buffers_give_the_same_database() {
+        let goal = Board::goal_board(3);
+        let tiles = [1, 2, 4, 5];
+        let expected = build(&goal, &tiles, &work_dir("large-buffer"), 1 << 16).to_bytes();
+
+        // A handful of states a run, so every layer is split over many runs and merged
+        let work = work_dir("small-buffer");
+        let mut layers = 0;
+        let database = build_pattern_database(&goal, &tiles, &work, 7, |_| layers += 1).unwrap();
+        assert!(layers > 5);
+        assert_eq!(database.to_bytes(), expected);
+    }
+
+    #[test]
+    fn interrupted_builds_resume() {
+        let goal = Board::goal_board(3);
+        let tiles = [3, 6, 7, 8];
+        let resume_from = 4;
+
+        // Keep a copy of the first few layers as a build makes them
+        let work = work_dir("interrupted");
+        let snapshot = work_dir("interrupted-snapshot");
+        fs::create_dir_all(&snapshot).unwrap();
+        let expected = build_pattern_database(&goal, &tiles, &work, 1 << 16, |report| {
+            if report.depth < resume_from {
+                let name = format!("layer-{}", report.depth);
+                fs::copy(Path::new(&work).join(&name), Path::new(&snapshot).join(&name)).unwrap();
+            }
+        }).unwrap().to_bytes();
+
+        // Left as a build killed part way through the next layer
+        let manifest = Manifest { goal: goal.state.clone(), tiles: tiles.to_vec(), layers: resume_from, complete: false };
+        fs::write(Path::new(&snapshot).join(MANIFEST), manifest.to_text()).unwrap();
+        fs::write(Path::new(&snapshot).join("run-0"), [1u8; 24]).unwrap();
+        fs::write(Path::new(&snapshot).join(format!("layer-{}.tmp", resume_from)), [2u8; 12]).unwrap();
+
+        let mut reports = Vec::new();
+        let database = build_pattern_database(&goal, &tiles, &snapshot, 1 << 16, |report| reports.push(report.clone())).unwrap();
+        assert_eq!(database.to_bytes(), expected);
+        assert!(reports.iter().all(|report| report.resumed == (report.depth < resume_from)));
+        assert!(reports.len() > resume_from);
+        assert!(!Path::new(&snapshot).exists());
+    }
+
+    #[test]
+    fn work_directories_of_other_patterns_are_rejected() {
+        let goal = Board::goal_board(3);
+        let work = work_dir("other-pattern");
+        fs::create_dir_all(&work).unwrap();
+
+        let manifest = Manifest { goal: goal.state.clone(), tiles: vec![1, 2, 3], layers: 2, complete: false };
+        fs::write(Path::new(&work).join(MANIFEST), manifest.to_text()).unwrap();
+
+        assert!(build_pattern_database(&goal, &[4, 5, 6], &work, 1 << 16, |_| ()).is_err());
+        assert!(build_pattern_database(&Board::new(3, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]), &[1, 2, 3], &work, 1 << 16, |_| ()).is_err());
+        fs::remove_dir_all(&work).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+// Perfect hashing of board states. A state with n cells is a permutation of 0..n, so it can be
+// mapped to a unique integer (its rank) and back. Ranks are u128, which covers every
+// permutation of up to 34 cells, so boards up to 5x5.
+//
+// Two numberings are provided:
+//  - rank/unrank number every permutation in lexicographic order, 0..n!
+//  - rank_solvable/unrank_solvable number only the solvable boards, densely 0..n!/2. These
+//    are the ones to use for tables indexed by board, ex. distance-to-goal tables
+//
+// rank_partial/unrank_partial number the placements of just a few tiles, for pattern databases
+
+pub const MAX_RANKED_CELLS: usize = 34;
+
+pub fn factorial(n: usize) -> Option<u128> {
+    let mut result: u128 = 1;
+    for value in 2..=n as u128 {
+        result = result.checked_mul(value)?;
+    }
+    Some(result)
+}
+
+// Number of solvable boards for a size x size board, None if it doesn't fit in a u128
+pub fn solvable_state_count(size: usize) -> Option<u128> {
+    if size * size > MAX_RANKED_CELLS {
+        return None;
+    }
+    factorial(size * size).map(|count| count / 2)
+}
+
+// Lexicographic rank of a permutation of 0..state.len()
+pub fn rank(state: &[u8]) -> u128 {
+    assert!(state.len() <= MAX_RANKED_CELLS, "board has too many cells to rank");
+
+    let mut result: u128 = 0;
+    for (index, tile) in state.iter().enumerate() {
+        let smaller_after = state[index+1..].iter().filter(|next| *next < tile).count() as u128;
+        result = result * (state.len() - index) as u128 + smaller_after;
+    }
+    result
+}
+
+// Inverse of rank, the permutation of 0..cells with the given lexicographic rank
+pub fn unrank(rank: u128, cells: usize) -> Vec<u8> {
+    assert!(cells <= MAX_RANKED_CELLS, "board has too many cells to rank");
+
+    // Peel off the mixed radix digits (Lehmer code), last position first
+    let mut digits = vec![0usize; cells];
+    let mut remaining = rank;
+    for index in (0..cells).rev() {
+        let radix = (cells - index) as u128;
+        digits[index] = (remaining % radix) as usize;
+        remaining /= radix;
+    }
+
+    let mut unused: Vec<u8> = (0..cells).map(|x| x as u8).collect();
+    digits.iter().map(|digit| unused.remove(*digit)).collect()
+}
+
+// Dense rank of a solvable board, in 0..solvable_state_count(size)
+//
+// A board is split into the position of the blank and the permutation of the remaining tiles.
+// For any blank position exactly half of the tile permutations are solvable, and lexicographic
+// neighbours 2k and 2k+1 only differ by swapping the last two tiles, so exactly one of each
+// pair is solvable. That gives blank * (n-1)!/2 + tile_rank/2 as a bijection.
+pub fn rank_solvable(state: &[u8], size: usize) -> u128 {
+    let cells = size * size;
+    let half  = half_tile_permutations(cells);
+
+    let blank = state.iter().position(|tile| *tile == 0).expect("board has no blank square");
+    let tiles: Vec<u8> = state.iter().filter(|tile| **tile != 0).map(|tile| tile - 1).collect();
+
+    blank as u128 * half + rank(&tiles) / 2
+}
+
+// Inverse of rank_solvable
+pub fn unrank_solvable(rank: u128, size: usize) -> Vec<u8> {
+    let cells = size * size;
+    let half  = half_tile_permutations(cells);
+
+    let blank = (rank / half) as usize;
+    let mut tiles = unrank(2 * (rank % half), cells - 1);
+
+    if inversion_parity(&tiles) != required_parity(blank, size) {
+        tiles.swap(cells - 3, cells - 2);
+    }
+
+    let mut state: Vec<u8> = tiles.iter().map(|tile| tile + 1).collect();
+    state.insert(blank, 0);
+    state
+}
+
+// Number of ways to place `placed` distinct tiles on `cells` cells, cells! / (cells - placed)!
+pub fn partial_permutation_count(cells: usize, placed: usize) -> u64 {
+    ((cells - placed + 1)..=cells).map(|value| value as u64).product()
+}
+
+// Dense rank of where a few tiles are, in 0..partial_permutation_count(cells, positions.len()).
+// Used to index pattern databases, positions[i] is the cell the i'th tile of the pattern is in
+pub fn rank_partial(positions: &[u8], cells: usize) -> u64 {
+    let mut result: u64 = 0;
+    for (index, position) in positions.iter().enumerate() {
+        let taken_before = positions[..index].iter().filter(|earlier| *earlier < position).count();
+        result = result * (cells - index) as u64 + (*position as usize - taken_before) as u64;
+    }
+    result
+}
+
+// Inverse of rank_partial
+pub fn unrank_partial(rank: u64, cells: usize, placed: usize) -> Vec<u8> {
+    let mut digits = vec![0usize; placed];
+    let mut remaining = rank;
+    for index in (0..placed).rev() {
+        let radix = (cells - index) as u64;
+        digits[index] = (remaining % radix) as usize;
+        remaining /= radix;
+    }
+
+    let mut free: Vec<u8> = (0..cells).map(|cell| cell as u8).collect();
+    digits.iter().map(|digit| free.remove(*digit)).collect()
+}
+
+fn half_tile_permutations(cells: usize) -> u128 {
+    assert!(cells >= 4, "board is too small to rank");
+    factorial(cells - 1).expect("board has too many cells to rank") / 2
+}
+
+fn inversion_parity(tiles: &[u8]) -> usize {
+    let mut inversions = 0;
+    for (index, tile) in tiles.iter().enumerate() {
+        inversions += tiles[index+1..].iter().filter(|next| *next < tile).count();
+    }
+    inversions % 2
+}
+
+// Parity the tile inversions need for the board to be solvable, see Board::is_solvable
+fn required_parity(blank: usize, size: usize) -> usize {
+    if !size.is_multiple_of(2) {
+        return 0;
+    }
+    (1 + blank / size) % 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    #[test]
+    fn rank_round_trips_every_small_permutation() {
+        for cells in [4, 9].iter() {
+            let count = factorial(*cells).unwrap();
+            for index in 0..count {
+                let state = unrank(index, *cells);
+                assert_eq!(rank(&state), index, "{:?}", state);
+            }
+        }
+    }
+
+    #[test]
+    fn rank_round_trips_4x4_boards() {
+        for seed in 0..1000 {
+            let board = Board::generate_board_seeded(4, seed);
+            assert_eq!(unrank(board.rank(), 16), board.state);
+            assert_eq!(Board::unrank(4, board.rank()).state, board.state);
+        }
+    }
+
+    #[test]
+    fn rank_solvable_is_a_bijection_onto_solvable_boards() {
+        for size in 2..=3 {
+            let count = solvable_state_count(size).unwrap();
+            for index in 0..count {
+                let state = unrank_solvable(index, size);
+                assert!(Board::new(size, state.clone()).is_solvable(), "{:?}", state);
+                assert_eq!(rank_solvable(&state, size), index, "{:?}", state);
+            }
+        }
+
+        let count = solvable_state_count(4).unwrap();
+        for seed in 0..1000 {
+            let board = Board::generate_board_seeded(4, seed);
+            let index = rank_solvable(&board.state, 4);
+            assert!(index < count);
+            assert_eq!(unrank_solvable(index, 4), board.state);
+        }
+    }
+
+    #[test]
+    fn rank_partial_round_trips() {
+        for size in 2..=4 {
+            let cells = size * size;
+            for placed in 1..=3.min(cells) {
+                for index in 0..partial_permutation_count(cells, placed) {
+                    let positions = unrank_partial(index, cells, placed);
+                    assert_eq!(positions.len(), placed);
+                    assert!(positions.iter().all(|position| (*position as usize) < cells));
+                    assert_eq!(rank_partial(&positions, cells), index, "{:?}", positions);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::VecDeque;
+
+use super::{Algorithm, Board, Metrics, Move, SolveError, SolverConfig, SolverSession};
+use super::heuristics::HeuristicKind;
+use super::ida_star::IdaStar;
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Side of the corner left for IDA* once the rest of the board is placed
+const CORE_SIZE: usize = 3;
+
+pub fn reduction_solver(start_board: &Board, goal_board: &Board) -> Vec<Move> {
+    reduction_solver_with_metrics(start_board, goal_board).path
+}
+
+pub fn reduction_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::Reduction, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// One piece of the reduction, see Reduction
+pub(crate) enum Step {
+    // Bring the tile that belongs in this cell to it, then leave it there
+    Place(usize),
+
+    // The last two cells of a row or column, which can't be filled one at a time without
+    // disturbing the first. The tile for `first` goes to `second` (the end of the line) and the
+    // tile for `second` next to it, one `across` further on. Then the blank steps in at `first`
+    // and moves `along` and `across`, sliding both tiles home together
+    PlacePair { first: usize, second: usize, along: Move, across: Move },
+
+    // Solve what's left, the bottom right corner, optimally
+    Core
+}
+
+// Solves any size of board the way people do: the top row is placed a tile at a time and then
+// left alone, then the left column, and so on until only a 3x3 corner is left, which IDA* solves
+// optimally. Each tile is moved by a breadth first search over where it and the blank are, so
+// every step takes time polynomial in the board size and the whole solve is bounded at about
+// n^6 for an nxn board, with O(n^3) moves. Solutions are far from optimal, passing them through
+// optimize::optimize_path takes out a good share of the slack.
+//
+// The goal's blank has to be in the bottom right corner, which is left for last
+pub(crate) struct Reduction {
+    size: usize,
+    tiles: Vec<u8>,
+    blank: usize,
+    goal: Vec<u8>,
+
+    // Cells that are finished and never moved again
+    locked: Vec<bool>,
+
+    plan: VecDeque<Step>,
+    core: Option<IdaStar>,
+
+    path: Vec<Move>,
+    nodes_expanded: usize
+}
+
+impl Reduction {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board) -> Result<Reduction, SolveError> {
+        let size = start_board.size;
+        check_goal(goal_board).map_err(SolveError::InvalidBoard)?;
+
+        Ok(Reduction {
+            size,
+            tiles: start_board.state.clone(),
+            blank: start_board.get_index_of(&0),
+            goal: goal_board.state.clone(),
+            locked: vec![false; size * size],
+            plan: plan(size),
+            core: None,
+            path: Vec::new(),
+            nodes_expanded: 0
+        })
+    }
+
+    fn neighbour(&self, cell: usize, the_move: Move) -> Option<usize> {
+        neighbour(self.size, cell, the_move)
+    }
+
+    fn apply(&mut self, moves: &[Move]) {
+        for the_move in moves {
+            let next = self.neighbour(self.blank, *the_move).expect("planned moves stay on the board");
+            self.tiles.swap(self.blank, next);
+            self.blank = next;
+        }
+        self.path.extend_from_slice(moves);
+    }
+
+    // Fewest moves that bring the tile in cell `from` to cell `to` without touching a locked
+    // cell, by breadth first search over where the tile and the blank are. None only when the tile
+    // is shut in a dead end, see PlacePair
+    fn move_tile(&mut self, from: usize, to: usize) -> Option<Vec<Move>> {
+        let cells = self.tiles.len();
+        let start = from * cells + self.blank;
+        let mut came_from = vec![None; cells * cells];
+        came_from[start] = Some((start, Move::None));
+
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+
+        while let Some(state) = queue.pop_front() {
+            let (tile, blank) = (state / cells, state % cells);
+            if tile == to {
+                return Some(trace(&came_from, state));
+            }
+            self.nodes_expanded += 1;
+
+            for the_move in MOVES.iter() {
+                let next_blank = match self.neighbour(blank, *the_move) {
+                    Some(next) if !self.locked[next] => next,
+                    _ => continue
+                };
+                let next_tile = if next_blank == tile { blank } else { tile };
+                let next = next_tile * cells + next_blank;
+
+                if came_from[next].is_none() {
+                    came_from[next] = Some((state, *the_move));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    // Fewest moves that bring the blank to the cell without touching a locked cell
+    fn move_blank(&mut self, to: usize) -> Vec<Move> {
+        let mut came_from = vec![None; self.tiles.len()];
+        came_from[self.blank] = Some((self.blank, Move::None));
+
+        let mut queue = VecDeque::new();
+        queue.push_back(self.blank);
+
+        while let Some(blank) = queue.pop_front() {
+            if blank == to {
+                return trace(&came_from, blank);
+            }
+            self.nodes_expanded += 1;
+
+            for the_move in MOVES.iter() {
+                if let Some(next) = self.neighbour(blank, *the_move) {
+                    if !self.locked[next] && came_from[next].is_none() {
+                        came_from[next] = Some((blank, *the_move));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        panic!("the unlocked cells are always connected");
+    }
+
+    fn position_of(&self, tile: u8) -> usize {
+        self.tiles.iter().position(|placed| *placed == tile).expect("every tile is on the board")
+    }
+
+    fn place(&mut self, tile: u8, to: usize) {
+        let moves = self.move_tile(self.position_of(tile), to).expect("a tile can always be moved while at least a 2x3 area is unlocked");
+        self.apply(&moves);
+        self.locked[to] = true;
+    }
+
+    fn run_step(&mut self, step: Step) {
+        match step {
+            Step::Place(cell) => self.place(self.goal[cell], cell),
+            Step::PlacePair { first, second, along, across } => {
+                let (first_tile, second_tile) = (self.goal[first], self.goal[second]);
+                if self.tiles[first] != first_tile || self.tiles[second] != second_tile {
+                    let staging = self.neighbour(second, across).expect("the board is bigger than the core");
+                    self.place(first_tile, second);
+
+                    match self.move_tile(self.position_of(second_tile), staging) {
+                        Some(moves) => self.apply(&moves),
+                        None => {
+                            // It's shut in at `first` behind the tile just placed (or next to it
+                            // with the blank shut in). Both come out by moving them together in
+                            // the 3x3 corner of unfinished cells around the pair
+                            let window = pair_window(self.size, first, second, along, across);
+                            if !window.contains(&self.blank) {
+                                let trapped = self.position_of(second_tile);
+                                self.locked[trapped] = true;
+                                let moves = self.move_blank(window[2]);
+                                self.apply(&moves);
+                                self.locked[trapped] = false;
+                            }
+                            self.locked[second] = false;
+
+                            let start = (self.position_of(first_tile), self.position_of(second_tile), self.blank);
+                            let moves = move_pair(self.size, &window, start, |first_at, second_at, _| (first_at, second_at) == (second, staging), &mut self.nodes_expanded);
+                            self.apply(&moves);
+                            self.locked[second] = true;
+                        }
+                    }
+                    self.locked[staging] = true;
+
+                    let moves = self.move_blank(first);
+                    self.apply(&moves);
+                    self.apply(&[along, across]);
+                    self.locked[staging] = false;
+                }
+                self.locked[first] = true;
+                self.locked[second] = true;
+            },
+            Step::Core => self.core = Some(self.core_search())
+        }
+    }
+
+    fn core_search(&self) -> IdaStar {
+        let (start, goal) = core_boards(self.size, &self.tiles, &self.goal);
+        IdaStar::new(&start, &goal, HeuristicKind::LinearConflict)
+    }
+}
+
+// Reduction, and the macro tables built from its plan, need the goal's blank in the corner that
+// is left for last
+pub(crate) fn check_goal(goal_board: &Board) -> Result<(), String> {
+    let size = goal_board.size;
+    let origin = size - size.min(CORE_SIZE);
+    let goal_blank = goal_board.get_index_of(&0);
+    if goal_blank / size < origin || goal_blank % size < origin {
+        return Err(format!("the goal's blank has to be in the bottom right {}x{} corner, which is solved last", CORE_SIZE, CORE_SIZE));
+    }
+    Ok(())
+}
+
+// The steps for a board of the size: the top row then the left column, over and over, then the core
+pub(crate) fn plan(size: usize) -> VecDeque<Step> {
+    let origin = size - size.min(CORE_SIZE);
+    let cell = |row: usize, col: usize| row * size + col;
+    let mut plan = VecDeque::new();
+
+    for line in 0..origin {
+        for col in line..size - 2 {
+            plan.push_back(Step::Place(cell(line, col)));
+        }
+        plan.push_back(Step::PlacePair { first: cell(line, size - 2), second: cell(line, size - 1), along: Move::Right, across: Move::Down });
+
+        for row in line + 1..size - 2 {
+            plan.push_back(Step::Place(cell(row, line)));
+        }
+        plan.push_back(Step::PlacePair { first: cell(size - 2, line), second: cell(size - 1, line), along: Move::Down, across: Move::Right });
+    }
+    plan.push_back(Step::Core);
+
+    plan
+}
+
+pub(crate) fn neighbour(size: usize, cell: usize, the_move: Move) -> Option<usize> {
+    let (row, col) = (cell / size, cell % size);
+    match the_move {
+        Move::Up    if row > 0        => Some(cell - size),
+        Move::Down  if row + 1 < size => Some(cell + size),
+        Move::Left  if col > 0        => Some(cell - 1),
+        Move::Right if col + 1 < size => Some(cell + 1),
+        _ => None
+    }
+}
+
+// `first`, `second` and the two lines of cells across from them and the cell before `first`
+pub(crate) fn pair_window(size: usize, first: usize, second: usize, along: Move, across: Move) -> Vec<usize> {
+    let before = neighbour(size, first, along.inverse()).expect("the pair is at the end of its line");
+    let mut window = vec![first, second];
+    for cell in [before, first, second].iter() {
+        let next = neighbour(size, *cell, across).expect("two lines are left across the pair");
+        window.push(next);
+        window.push(neighbour(size, next, across).expect("two lines are left across the pair"));
+    }
+    window
+}
+
+// Fewest moves from `start` (where two tiles and the blank are) to where `done` holds, moving
+// only inside the window. Everything in `start` has to be in the window already
+pub(crate) fn move_pair<F>(size: usize, window: &[usize], start: (usize, usize, usize), done: F, nodes_expanded: &mut usize) -> Vec<Move>
+    where F: Fn(usize, usize, usize) -> bool
+{
+    let cells = window.len();
+    let local = |cell: usize| window.iter().position(|inside| *inside == cell);
+    let encode = |first: usize, second: usize, blank: usize| (first * cells + second) * cells + blank;
+
+    let start = encode(local(start.0).unwrap(), local(start.1).unwrap(), local(start.2).unwrap());
+    let mut came_from = vec![None; cells * cells * cells];
+    came_from[start] = Some((start, Move::None));
+
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(state) = queue.pop_front() {
+        let (first, second, blank) = (state / cells / cells, state / cells % cells, state % cells);
+        if done(window[first], window[second], window[blank]) {
+            return trace(&came_from, state);
+        }
+        *nodes_expanded += 1;
+
+        for the_move in MOVES.iter() {
+            let next_blank = match neighbour(size, window[blank], *the_move).and_then(local) {
+                Some(next) => next,
+                None => continue
+            };
+            let slide = |tile: usize| if tile == next_blank { blank } else { tile };
+            let next = encode(slide(first), slide(second), next_blank);
+
+            if came_from[next].is_none() {
+                came_from[next] = Some((state, *the_move));
+                queue.push_back(next);
+            }
+        }
+    }
+    panic!("two tiles can always be arranged in a 3x3 window less a corner");
+}
+
+// The corner as a board of its own, given everything else is in place, with its goal. Its tiles
+// are renumbered 1 up in goal order. Moves in it are moves of the whole board, and it can reach
+// its goal when the whole board can
+pub(crate) fn core_boards(size: usize, tiles: &[u8], goal: &[u8]) -> (Board, Board) {
+    let core_size = size.min(CORE_SIZE);
+    let origin = size - core_size;
+    let cells: Vec<usize> = (origin..size).flat_map(|row| (origin..size).map(move |col| row * size + col)).collect();
+
+    let mut labels = vec![0u8; tiles.len()];
+    let mut next = 1;
+    for cell in &cells {
+        if goal[*cell] != 0 {
+            labels[goal[*cell] as usize] = next;
+            next += 1;
+        }
+    }
+
+    let start = Board::new(core_size, cells.iter().map(|cell| labels[tiles[*cell] as usize]).collect());
+    let goal = Board::new(core_size, cells.iter().map(|cell| labels[goal[*cell] as usize]).collect());
+    (start, goal)
+}
+
+fn trace(came_from: &[Option<(usize, Move)>], mut state: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some((previous, the_move)) = came_from[state] {
+        if the_move == Move::None {
+            break;
+        }
+        moves.push(the_move);
+        state = previous;
+    }
+    moves.reverse();
+    moves
+}
+
+impl Search for Reduction {
+    // Runs whole steps, so it can go over the budget by one tile's search
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded().saturating_add(budget);
+
+        while self.nodes_expanded() < target {
+            if let Some(core) = self.core.as_mut() {
+                if let SearchStatus::Solved(moves) = core.step(target - self.nodes_expanded - core.nodes_expanded()) {
+                    let mut path = self.path.clone();
+                    path.extend(moves);
+                    return SearchStatus::Solved(path);
+                }
+                continue;
+            }
+
+            let step = self.plan.pop_front().expect("the plan ends with the core");
+            self.run_step(step);
+        }
+
+        SearchStatus::InProgress
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded + self.core.as_ref().map_or(0, |core| core.nodes_expanded())
+    }
+
+    // Just the board, and the core's path once it's being solved
+    fn stored_states(&self) -> usize {
+        1 + self.core.as_ref().map_or(0, |core| core.stored_states())
+    }
+
+    // The moves so far, which place everything outside the core that has been placed
+    fn best_path(&self) -> Vec<Move> {
+        let mut path = self.path.clone();
+        if let Some(core) = &self.core {
+            path.extend(core.best_path());
+        }
+        path
+    }
+
+    // Not a bound on the whole solution, reduction isn't optimal
+    fn f_bound(&self) -> Option<usize> {
+        None
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.core.as_ref().map_or(0, |core| core.frontier_sizes()[0])]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_boards_are_solved() {
+        for size in 4..=8 {
+            let goal = Board::goal_board(size);
+            for seed in 0..5 {
+                let board = Board::generate_board_seeded(size, 1000 * size as u64 + seed);
+                let path = reduction_solver(&board, &goal);
+                assert_eq!(board.apply_moves(&path).unwrap().state, goal.state, "{:?}", board.state);
+            }
+        }
+    }
+}
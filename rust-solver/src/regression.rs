@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use super::{Board, Metrics, bidirectional_solver_with_metrics};
+
+// A single line of a metrics file, ex.
+// 8 9 0 11 10 1 3 6 12 7 4 13 2 15 5 14 , 8.295992ms, 10452
+// Older files don't record the node count, so it is optional
+pub struct MetricsRecord {
+    pub board: Board,
+    pub running_time: Duration,
+    pub nodes_expanded: Option<usize>
+}
+
+pub struct RegressionResult {
+    // The recorded run we are comparing against
+    pub baseline: MetricsRecord,
+
+    // The run we just made
+    pub current: MetricsRecord,
+
+    // Whether the new solution actually takes the board to the goal
+    pub verified: bool,
+
+    // current / baseline, anything above the allowed ratio is a regression
+    pub time_ratio: f64,
+    pub node_ratio: Option<f64>,
+
+    pub time_regressed: bool,
+    pub nodes_regressed: bool
+}
+
+impl RegressionResult {
+    pub fn passed(&self) -> bool {
+        self.verified && !self.time_regressed && !self.nodes_regressed
+    }
+
+    pub fn display(&self) {
+        self.baseline.board.print_flat();
+        print!(", {:?} -> {:?} (x{:.2})", self.baseline.running_time, self.current.running_time, self.time_ratio);
+
+        if let (Some(baseline), Some(current), Some(ratio)) = (self.baseline.nodes_expanded, self.current.nodes_expanded, self.node_ratio) {
+            print!(", {} -> {} nodes (x{:.2})", baseline, current, ratio);
+        }
+
+        if !self.verified {
+            print!(", INVALID SOLUTION");
+        }
+        if self.time_regressed {
+            print!(", TIME REGRESSION");
+        }
+        if self.nodes_regressed {
+            print!(", NODE REGRESSION");
+        }
+        println!();
+    }
+}
+
+pub fn parse_metrics(contents: &str) -> Result<Vec<MetricsRecord>, String> {
+    let mut records = Vec::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(format!("line {}: expected 'tiles , time[, nodes]'", line_number + 1));
+        }
+
+        let board = parse_board(fields[0]).map_err(|err| format!("line {}: {}", line_number + 1, err))?;
+        let running_time = parse_duration(fields[1]).map_err(|err| format!("line {}: {}", line_number + 1, err))?;
+
+        let mut nodes_expanded = None;
+        if fields.len() == 3 {
+            let nodes = fields[2].parse::<usize>().map_err(|_| format!("line {}: invalid node count '{}'", line_number + 1, fields[2]))?;
+            nodes_expanded = Some(nodes);
+        }
+
+        records.push(MetricsRecord { board, running_time, nodes_expanded });
+    }
+
+    Ok(records)
+}
+
+// Writes records in the same layout the solver prints, so the output can be used as the next baseline
+pub fn format_metrics(records: &[MetricsRecord]) -> String {
+    let mut output = String::new();
+
+    for record in records {
+        for tile in &record.board.state {
+            output.push_str(&format!("{} ", tile));
+        }
+        output.push_str(&format!(", {:?}", record.running_time));
+        if let Some(nodes) = record.nodes_expanded {
+            output.push_str(&format!(", {}", nodes));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+// Re-solve every recorded board and compare against the recording. A board regresses
+// when its solve time (or node count, when recorded) grows by more than max_ratio
+pub fn run_regression(baseline: Vec<MetricsRecord>, max_ratio: f64) -> Vec<RegressionResult> {
+    let mut results = Vec::new();
+
+    for record in baseline {
+        let goal = Board::goal_board(record.board.size);
+        let metrics = bidirectional_solver_with_metrics(&record.board, &goal);
+        let verified = is_valid_solution(&record.board, &goal, &metrics);
+
+        let time_ratio = metrics.running_time.as_secs_f64() / record.running_time.as_secs_f64().max(f64::EPSILON);
+        let node_ratio = record.nodes_expanded.map(|nodes| metrics.nodes_expanded as f64 / (nodes.max(1) as f64));
+
+        let current = MetricsRecord {
+            board: record.board.clone(),
+            running_time: metrics.running_time,
+            nodes_expanded: Some(metrics.nodes_expanded)
+        };
+
+        results.push(RegressionResult {
+            baseline: record,
+            current,
+            verified,
+            time_ratio,
+            node_ratio,
+            time_regressed: time_ratio > max_ratio,
+            nodes_regressed: node_ratio.is_some_and(|ratio| ratio > max_ratio)
+        });
+    }
+
+    results
+}
+
+fn is_valid_solution(start: &Board, goal: &Board, metrics: &Metrics) -> bool {
+    match start.apply_moves(&metrics.path) {
+        Some(board) => board == *goal,
+        None => false
+    }
+}
+
+fn parse_board(field: &str) -> Result<Board, String> {
+    let mut state = Vec::new();
+    for tile in field.split_whitespace() {
+        state.push(tile.parse::<u8>().map_err(|_| format!("invalid tile '{}'", tile))?);
+    }
+
+    let size = (state.len() as f64).sqrt() as usize;
+    if size < 2 || size * size != state.len() {
+        return Err(format!("{} tiles is not a square board", state.len()));
+    }
+
+    let board = Board::new(size, state);
+    board.validate()?;
+
+    // run_regression solves to the standard goal
+    if !board.is_solvable() {
+        return Err("the board can't reach the goal".to_string());
+    }
+    Ok(board)
+}
+
+// Parses the Debug output of a Duration, ex. 8.295992ms, 1.2s, 850µs
+fn parse_duration(field: &str) -> Result<Duration, String> {
+    let units = [("ns", 1e-9), ("µs", 1e-6), ("us", 1e-6), ("ms", 1e-3), ("s", 1.0)];
+
+    for (suffix, scale) in units.iter() {
+        if let Some(value) = field.strip_suffix(suffix) {
+            let value = value.parse::<f64>().map_err(|_| format!("invalid duration '{}'", field))?;
+            return Ok(Duration::from_secs_f64(value * scale));
+        }
+    }
+
+    Err(format!("invalid duration '{}'", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn good_rows_are_parsed() {
+        let contents = "8 9 0 11 10 1 3 6 12 7 4 13 2 15 5 14 , 8.295992ms, 10452\n\n1 2 3 4 5 6 7 0 8 , 850µs\n1 2 3 4 5 6 0 7 8, 1.5s, 3\n";
+        let records = parse_metrics(contents).unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].board.size, 4);
+        assert_eq!(records[0].board.state, vec![8, 9, 0, 11, 10, 1, 3, 6, 12, 7, 4, 13, 2, 15, 5, 14]);
+        assert_eq!(records[0].running_time, Duration::from_nanos(8_295_992));
+        assert_eq!(records[0].nodes_expanded, Some(10452));
+        assert_eq!(records[1].board.size, 3);
+        assert_eq!(records[1].running_time, Duration::from_micros(850));
+        assert_eq!(records[1].nodes_expanded, None);
+        assert_eq!(records[2].running_time, Duration::from_millis(1500));
+
+        // Written back out, the records parse to the same thing
+        let reparsed = parse_metrics(&format_metrics(&records)).unwrap();
+        assert_eq!(reparsed.len(), 3);
+        for (record, reparsed) in records.iter().zip(&reparsed) {
+            assert_eq!(record.board.state, reparsed.board.state);
+            assert_eq!(record.running_time, reparsed.running_time);
+            assert_eq!(record.nodes_expanded, reparsed.nodes_expanded);
+        }
+    }
+
+    #[test]
+    fn bad_rows_are_rejected() {
+        let rows = [
+            "1 2 3 4 5 6 7 8 0",
+            "1 2 3 4 5 6 7 8 0 , 1ms, 2, 3",
+            "1 2 3 4 5 6 7 8 , 1ms",
+            "1 2 3 4 5 6 7 x 0 , 1ms",
+            "1 2 3 4 5 6 7 7 0 , 1ms",
+            "1 2 3 4 5 6 7 9 0 , 1ms",
+            "2 1 3 4 5 6 7 8 0 , 1ms",
+            "0 , 1ms",
+            "1 2 3 4 5 6 7 8 0 , 1 hour",
+            "1 2 3 4 5 6 7 8 0 , 1ms, many"
+        ];
+
+        for row in rows.iter() {
+            let contents = format!("1 2 3 4 5 6 7 0 8 , 1ms\n{}\n", row);
+            let err = parse_metrics(&contents).err().unwrap_or_else(|| panic!("'{}' was accepted", row));
+            assert!(err.starts_with("line 2:"), "{}", err);
+        }
+    }
+}
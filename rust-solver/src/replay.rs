@@ -0,0 +1,55 @@
+use super::{Board, Move, SolveError, lookup_solver};
+use super::macro_table::MacroTable;
+use super::solver::check_boards;
+
+// Solves a board by replaying a macro table built for its size and goal: each stage looks up the
+// macro for where its tile is and applies it, then the core is read off the table's distance
+// table. There's no search, so every board takes at most MacroTable::max_solution_length moves
+// and time in proportion. As with lookup_solver, a corrupt table (ex. a damaged file) gives
+// SolveError::InvalidTable once replaying it goes wrong, MacroTable::verify checks a whole table
+// up front but has to build it again to do it
+pub fn macro_solver(table: &MacroTable, start_board: &Board) -> Result<Vec<Move>, SolveError> {
+    check_boards(start_board, table.goal())?;
+
+    let mut board = Board::new(start_board.size, start_board.state.clone());
+    let mut path = Vec::new();
+
+    for stage in 0..table.stage_count() {
+        let moves = table.macro_for(stage, &board)
+            .ok_or_else(|| SolveError::InvalidTable(format!("stage {} has no macro for {:?}", stage, board.state)))?;
+        board = board.apply_moves(moves)
+            .ok_or_else(|| SolveError::InvalidTable(format!("stage {}'s macro for {:?} leaves the board", stage, board.state)))?;
+        path.extend_from_slice(moves);
+    }
+
+    // Once the stages have done their job the core can always be solved, so a problem with it
+    // is the table's as well
+    let core_path = lookup_solver(table.core(), &table.core_board(&board)).map_err(|err| match err {
+        SolveError::InvalidTable(reason) => SolveError::InvalidTable(reason),
+        err => SolveError::InvalidTable(format!("the stages leave a core that can't be looked up, {}", err))
+    })?;
+    if board.apply_moves(&core_path).is_none_or(|end| end != *table.goal()) {
+        return Err(SolveError::InvalidTable(format!("the stages leave {:?} with tiles out of place", board.state)));
+    }
+
+    path.extend(core_path);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_boards_are_solved() {
+        for size in 4..=8 {
+            let goal = Board::goal_board(size);
+            let table = MacroTable::build(&goal).unwrap();
+            for seed in 0..20 {
+                let board = Board::generate_board_seeded(size, 1000 * size as u64 + seed);
+                let path = macro_solver(&table, &board).unwrap();
+                assert_eq!(board.apply_moves(&path).unwrap().state, goal.state, "{:?}", board.state);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::Move;
+
+pub enum SearchStatus {
+    // The budget ran out before a solution was found, step again to carry on
+    InProgress,
+
+    // Moves of the blank square from the start board to the goal board
+    Solved(Vec<Move>),
+
+    // Every board the search can reach has been expanded without finding the goal, stepping
+    // again does nothing. Boards that pass check_boards can always reach the goal
+    Exhausted
+}
+
+// A search that can be run a little at a time. Each call to step picks up exactly where the last
+// one stopped, which lets callers interleave solving with other work (ex. keeping a browser
+// responsive) or give up part way through
+pub trait Search {
+    // Keep searching until at least `budget` more nodes have been expanded or a solution is found
+    fn step(&mut self, budget: usize) -> SearchStatus;
+
+    // Total nodes expanded so far, counted the same way as Metrics::nodes_expanded
+    fn nodes_expanded(&self) -> usize;
+
+    // Boards currently held in memory, what SearchLimits::max_stored_states caps
+    fn stored_states(&self) -> usize;
+
+    // Moves from the start to the board closest to the goal (by the heuristic) seen so far, the
+    // partial result when a search is stopped early. May already reach the goal if a solution
+    // was found but not yet proven good enough
+    fn best_path(&self) -> Vec<Move>;
+
+    // IDA*'s current threshold, or the f (moves so far plus estimate) at the front of the open
+    // list. For the bidirectional search it's the lower bound it is working to meet
+    fn f_bound(&self) -> Option<usize>;
+
+    // How many boards are waiting to be expanded, one count per direction searched. For IDA*
+    // that's the depth of the current path
+    fn frontier_sizes(&self) -> Vec<usize>;
+
+    // What a solution path costs in the metric this search minimises, one per move by default
+    fn path_cost(&self, path: &[Move]) -> usize {
+        path.len()
+    }
+}
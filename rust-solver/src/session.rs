@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use web_time::{Duration, Instant};
+
+use super::{Algorithm, Board, CancelToken, Metrics, Move, SolverConfig, SolveError};
+use super::limits::{Limit, SearchLimits};
+use super::bfs::{BidirectionalBfs, BreadthFirstSearch};
+use super::bidirectional::BidirectionalSearch;
+use super::frontier::FrontierAStar;
+use super::ida_star::IdaStar;
+use super::pdb::AdditivePatterns;
+use super::reduction::Reduction;
+use super::slide_tile::SlideTileIdaStar;
+use super::weighted_a_star::WeightedAStar;
+use super::search::{Search, SearchStatus};
+use super::solver::check_boards;
+
+pub enum SessionStatus {
+    // Still searching, call step again
+    Running,
+
+    Solved(Metrics),
+
+    // Stopped by cancel() or the config's cancel token before a solution was found
+    Cancelled,
+
+    // Stopped on reaching one of the search limits. The metrics are for the work done so far, with
+    // the path to the board found closest to the goal (see Search::best_path)
+    Exhausted(Limit, Metrics),
+
+    // The search ran out of boards without reaching the goal (see SearchStatus::Exhausted)
+    Unsolvable
+}
+
+// A snapshot of how a session is going, see SolverSession::progress
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub nodes_expanded: usize,
+    pub stored_states: usize,
+
+    // See Search::f_bound and Search::frontier_sizes. The bidirectional search has two frontier
+    // sizes, forward then backward, the others one
+    pub f_bound: Option<usize>,
+    pub frontier_sizes: Vec<usize>,
+
+    // Time spent searching so far
+    pub running_time: Duration
+}
+
+// How many nodes the search runs between checks of the stored state and time limits, and of
+// the cancel token
+const LIMIT_CHECK_INTERVAL: usize = 4096;
+
+// A solve that runs a slice at a time, so it can be spread over idle callbacks or a worker's
+// message loop and abandoned part way through
+pub struct SolverSession {
+    search: Box<dyn Search>,
+    limits: SearchLimits,
+    cancel: Option<CancelToken>,
+
+    // Time spent inside step, not wall clock time since the session was created
+    running_time: Duration,
+
+    status: SessionStatus
+}
+
+impl SolverSession {
+    pub fn new(start_board: &Board, goal_board: &Board, config: &SolverConfig) -> Result<SolverSession, SolveError> {
+        check_boards(start_board, goal_board)?;
+
+        let search: Box<dyn Search> = match config.algorithm {
+            Algorithm::Bidirectional    => Box::new(BidirectionalSearch::new(start_board, goal_board, config.heuristic, config.epsilon)),
+            Algorithm::IdaStar          => Box::new(IdaStar::new(start_board, goal_board, config.heuristic)),
+            Algorithm::SlideTileIdaStar => Box::new(SlideTileIdaStar::new(start_board, goal_board)),
+            Algorithm::WeightedAStar    => Box::new(WeightedAStar::new(start_board, goal_board, config.heuristic, config.epsilon)),
+            Algorithm::Bfs              => Box::new(BreadthFirstSearch::new(start_board, goal_board)),
+            Algorithm::BidirectionalBfs => Box::new(BidirectionalBfs::new(start_board, goal_board)),
+            Algorithm::FrontierAStar    => Box::new(FrontierAStar::new(start_board, goal_board, config.heuristic)?),
+            Algorithm::PatternIdaStar   => Box::new(IdaStar::with_patterns(start_board, goal_board, patterns_for(goal_board, config)?)),
+            Algorithm::Reduction        => Box::new(Reduction::new(start_board, goal_board)?)
+        };
+
+        Ok(SolverSession {
+            search,
+            limits: config.limits,
+            cancel: config.cancel.clone(),
+            running_time: Duration::from_secs(0),
+            status: SessionStatus::Running
+        })
+    }
+
+    // Expand roughly budget_nodes more nodes (see Search::step). Does nothing once the session
+    // has finished, been cancelled or reached a limit
+    pub fn step(&mut self, budget_nodes: usize) -> &SessionStatus {
+        let target = self.search.nodes_expanded().saturating_add(budget_nodes);
+
+        while let SessionStatus::Running = self.status {
+            let nodes_expanded = self.search.nodes_expanded();
+            if nodes_expanded >= target {
+                break;
+            }
+
+            if self.cancel.as_ref().is_some_and(|cancel| cancel.is_cancelled()) {
+                self.status = SessionStatus::Cancelled;
+                break;
+            }
+            if let Some(limit) = self.limits.reached(nodes_expanded, self.search.stored_states(), self.running_time) {
+                let path = self.search.best_path();
+                self.status = SessionStatus::Exhausted(limit, self.metrics(path));
+                break;
+            }
+
+            // Only break the work up when there is a limit or token to check
+            let mut slice = (target - nodes_expanded).min(self.limits.nodes_left(nodes_expanded));
+            if self.limits.max_stored_states.is_some() || self.limits.time_limit.is_some() || self.cancel.is_some() {
+                slice = slice.min(LIMIT_CHECK_INTERVAL);
+            }
+
+            let start = Instant::now();
+            let status = self.search.step(slice);
+            self.running_time += start.elapsed();
+
+            match status {
+                SearchStatus::Solved(path) => self.status = SessionStatus::Solved(self.metrics(path)),
+                SearchStatus::Exhausted => self.status = SessionStatus::Unsolvable,
+                SearchStatus::InProgress => ()
+            }
+        }
+
+        &self.status
+    }
+
+    fn metrics(&self, path: Vec<Move>) -> Metrics {
+        Metrics {
+            cost_of_path: self.search.path_cost(&path),
+            path,
+            nodes_expanded: self.search.nodes_expanded(),
+            running_time: self.running_time
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        if let SessionStatus::Running = self.status {
+            self.status = SessionStatus::Cancelled;
+        }
+    }
+
+    // Search until solved, cancelled or a limit is reached. The boards have already been checked
+    // so this always finishes
+    pub fn run(mut self) -> Result<Metrics, SolveError> {
+        self.step(usize::MAX);
+
+        match self.status {
+            SessionStatus::Solved(metrics) => Ok(metrics),
+            SessionStatus::Exhausted(limit, metrics) => Err(SolveError::BudgetExhausted(limit, Box::new(metrics))),
+            SessionStatus::Cancelled => Err(SolveError::Cancelled(Box::new(self.metrics(self.search.best_path())))),
+            SessionStatus::Unsolvable => Err(SolveError::Unsolvable),
+            SessionStatus::Running => unreachable!("an unlimited step always finishes the search")
+        }
+    }
+
+    pub fn stored_states(&self) -> usize {
+        self.search.stored_states()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            nodes_expanded: self.search.nodes_expanded(),
+            stored_states: self.search.stored_states(),
+            f_bound: self.search.f_bound(),
+            frontier_sizes: self.search.frontier_sizes(),
+            running_time: self.running_time
+        }
+    }
+
+    pub fn status(&self) -> &SessionStatus {
+        &self.status
+    }
+
+    pub fn nodes_expanded(&self) -> usize {
+        self.search.nodes_expanded()
+    }
+
+    pub fn running_time(&self) -> Duration {
+        self.running_time
+    }
+}
+
+// The config's pattern databases, checking they're for this goal
+fn patterns_for(goal_board: &Board, config: &SolverConfig) -> Result<Arc<AdditivePatterns>, SolveError> {
+    match &config.patterns {
+        Some(patterns) if patterns.goal() == goal_board => Ok(patterns.clone()),
+        Some(patterns) => Err(SolveError::InvalidBoard(format!("the pattern databases are for goal {:?}", patterns.goal().state))),
+        None => Err(SolveError::InvalidBoard(format!("{} needs pattern databases", config.algorithm.name())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hard_board() -> Board {
+        Board::new(4, vec![11, 15, 3, 12, 2, 8, 10, 1, 4, 6, 5, 14, 13, 7, 9, 0])
+    }
+
+    #[test]
+    fn cancelled_tokens_stop_the_solve() {
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let config = SolverConfig { cancel: Some(cancel), ..SolverConfig::default() };
+        let result = SolverSession::new(&hard_board(), &Board::goal_board(4), &config).unwrap().run();
+        assert!(matches!(result, Err(SolveError::Cancelled(_))));
+    }
+
+    #[test]
+    fn node_limits_stop_the_solve_with_the_nodes_counted() {
+        for algorithm in [Algorithm::Bidirectional, Algorithm::IdaStar, Algorithm::WeightedAStar].iter() {
+            let config = SolverConfig {
+                algorithm: *algorithm,
+                limits: SearchLimits { max_nodes: Some(1000), ..SearchLimits::default() },
+                ..SolverConfig::default()
+            };
+
+            match SolverSession::new(&hard_board(), &Board::goal_board(4), &config).unwrap().run() {
+                Err(SolveError::BudgetExhausted(Limit::Nodes, metrics)) => {
+                    assert!(metrics.nodes_expanded >= 1000, "{:?}: {} nodes", algorithm, metrics.nodes_expanded);
+                    assert!(hard_board().apply_moves(&metrics.path).is_some());
+                },
+                Err(err) => panic!("{:?}: {}", algorithm, err),
+                Ok(_) => panic!("{:?}: solved within the node limit", algorithm)
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use super::{Algorithm, Board, Metrics, Move, Slide, SolverConfig, SolverSession};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::search::{Search, SearchStatus};
+
+const DIRECTIONS: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// Admissible estimate of the slides left to reach the goal. A slide moves at most size-1 tiles
+// one square each, all along the same axis, so the total row distance and the total column
+// distance can each only shrink by size-1 per slide
+pub struct SlideDistance {
+    size: usize,
+    goal_distance: GoalDistance
+}
+
+impl SlideDistance {
+    pub fn new(goal_board: &Board) -> SlideDistance {
+        SlideDistance {
+            size: goal_board.size,
+            goal_distance: GoalDistance::new(goal_board)
+        }
+    }
+
+    pub fn estimate(&self, state: &[u8]) -> i32 {
+        let size = self.size;
+        let mut rows = 0;
+        let mut cols = 0;
+
+        for (index, tile) in state.iter().enumerate() {
+            if *tile != 0 {
+                let goal = self.goal_distance.goal_position(*tile);
+                rows += ((index / size) as i32 - (goal / size) as i32).abs();
+                cols += ((index % size) as i32 - (goal % size) as i32).abs();
+            }
+        }
+
+        let per_slide = size as i32 - 1;
+        (rows + per_slide - 1) / per_slide + (cols + per_slide - 1) / per_slide
+    }
+}
+
+// IDA* counting moves in the slide-tile metric, so the first solution found has the fewest
+// slides. Laid out like IdaStar: an explicit stack of the path plus the next slide to try at
+// each depth, with the board updated in place.
+//
+// Two slides in a row along the same axis are never needed (they add up to one slide, or cancel
+// out), so after a vertical slide only horizontal ones are tried and the other way around
+pub(crate) struct SlideTileIdaStar {
+    size: usize,
+    tiles: Vec<u8>,
+    blank: usize,
+
+    heuristic: SlideDistance,
+    hval: i32,
+
+    bound: i32,
+    next_bound: i32,
+
+    path: Vec<Slide>,
+
+    // Next slide to try, one entry per depth. Slide number i goes in DIRECTIONS[i / (size-1)]
+    // and moves 1 + i % (size-1) tiles
+    next_slide: Vec<usize>,
+
+    // Path to the board with the smallest estimate seen so far, and that estimate
+    best_path: Vec<Slide>,
+    best_h: i32,
+
+    nodes_expanded: usize
+}
+
+impl SlideTileIdaStar {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board) -> SlideTileIdaStar {
+        let heuristic = SlideDistance::new(goal_board);
+        let hval = heuristic.estimate(&start_board.state);
+
+        SlideTileIdaStar {
+            size: start_board.size,
+            tiles: start_board.state.clone(),
+            blank: start_board.get_index_of(&0),
+            heuristic,
+            hval,
+            bound: hval,
+            next_bound: i32::MAX,
+            path: Vec::new(),
+            next_slide: vec![0],
+            best_path: Vec::new(),
+            best_h: hval,
+            nodes_expanded: 0
+        }
+    }
+
+    // How far the blank can travel in a direction before it hits the edge
+    fn room(&self, direction: Move) -> usize {
+        let (row, col) = (self.blank / self.size, self.blank % self.size);
+        match direction {
+            Move::Up    => row,
+            Move::Down  => self.size - 1 - row,
+            Move::Left  => col,
+            Move::Right => self.size - 1 - col,
+            Move::None  => 0
+        }
+    }
+
+    fn slide(&mut self, slide: Slide) {
+        let step = match slide.direction {
+            Move::Up    => -(self.size as isize),
+            Move::Down  => self.size as isize,
+            Move::Left  => -1,
+            Move::Right => 1,
+            Move::None  => return
+        };
+
+        for _ in 0..slide.tiles {
+            let target = (self.blank as isize + step) as usize;
+            self.tiles[self.blank] = self.tiles[target];
+            self.tiles[target] = 0;
+            self.blank = target;
+        }
+        self.hval = self.heuristic.estimate(&self.tiles);
+    }
+}
+
+fn is_vertical(direction: Move) -> bool {
+    direction == Move::Up || direction == Move::Down
+}
+
+impl Search for SlideTileIdaStar {
+    // Never finishes if the goal can't be reached, the caller must make sure the board is solvable
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+        let per_direction = self.size - 1;
+
+        loop {
+            if self.hval == 0 {
+                return SearchStatus::Solved(Slide::slides_to_moves(&self.path));
+            }
+            if self.nodes_expanded >= target {
+                return SearchStatus::InProgress;
+            }
+
+            let depth = self.path.len();
+            let slide_index = self.next_slide[depth];
+
+            if slide_index == DIRECTIONS.len() * per_direction {
+                // Every child of this board has been tried
+                if depth == 0 {
+                    self.bound = self.next_bound;
+                    self.next_bound = i32::MAX;
+                    self.next_slide[0] = 0;
+                }
+                else {
+                    self.next_slide.pop();
+                    let last = self.path.pop().unwrap();
+                    self.slide(last.inverse());
+                }
+                continue;
+            }
+
+            self.next_slide[depth] += 1;
+            let slide = Slide { direction: DIRECTIONS[slide_index / per_direction], tiles: 1 + slide_index % per_direction };
+
+            let same_axis = self.path.last().is_some_and(|last| is_vertical(last.direction) == is_vertical(slide.direction));
+            if same_axis || slide.tiles > self.room(slide.direction) {
+                continue;
+            }
+
+            self.slide(slide);
+            self.nodes_expanded += 1;
+
+            let fval = depth as i32 + 1 + self.hval;
+            if fval > self.bound {
+                self.next_bound = self.next_bound.min(fval);
+                self.slide(slide.inverse());
+                continue;
+            }
+
+            self.path.push(slide);
+            if self.hval < self.best_h {
+                self.best_h = self.hval;
+                self.best_path = self.path.clone();
+            }
+            self.next_slide.push(0);
+        }
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    // Only the current path is kept
+    fn stored_states(&self) -> usize {
+        self.path.len() + 1
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        Some(self.bound as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.path.len()]
+    }
+
+    fn best_path(&self) -> Vec<Move> {
+        Slide::slides_to_moves(&self.best_path)
+    }
+
+    // Consecutive slides are always along different axes, so grouping the moves gets them back
+    fn path_cost(&self, path: &[Move]) -> usize {
+        Slide::moves_to_slides(path).len()
+    }
+}
+
+// Fewest slides from the start board to the goal board
+pub fn slide_tile_solver(start_board: &Board, goal_board: &Board) -> Vec<Slide> {
+    Slide::moves_to_slides(&slide_tile_solver_with_metrics(start_board, goal_board).path)
+}
+
+// Metrics::path is in single tile moves, cost_of_path is the number of slides
+pub fn slide_tile_solver_with_metrics(start_board: &Board, goal_board: &Board) -> Metrics {
+    let config = SolverConfig { algorithm: Algorithm::SlideTileIdaStar, heuristic: HeuristicKind::Manhattan, ..SolverConfig::default() };
+    SolverSession::new(start_board, goal_board, &config).and_then(|session| session.run()).unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::ranking::{rank_solvable, solvable_state_count, unrank_solvable};
+
+    // Fewest slides from every 3x3 board to the goal, indexed by rank_solvable
+    fn slide_distances() -> Vec<u8> {
+        let goal = Board::goal_board(3);
+        let mut distances = vec![u8::MAX; solvable_state_count(3).unwrap() as usize];
+        distances[rank_solvable(&goal.state, 3) as usize] = 0;
+
+        let mut queue = VecDeque::new();
+        queue.push_back(goal);
+
+        while let Some(board) = queue.pop_front() {
+            let distance = distances[rank_solvable(&board.state, 3) as usize];
+            for direction in DIRECTIONS.iter() {
+                for tiles in 1..3 {
+                    if let Some(child) = board.apply_slide(Slide { direction: *direction, tiles }) {
+                        let index = rank_solvable(&child.state, 3) as usize;
+                        if distances[index] == u8::MAX {
+                            distances[index] = distance + 1;
+                            queue.push_back(Board::new(3, child.state));
+                        }
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    #[test]
+    fn slide_estimates_never_overestimate() {
+        let goal = Board::goal_board(3);
+        let heuristic = SlideDistance::new(&goal);
+
+        for (index, distance) in slide_distances().iter().enumerate() {
+            let state = unrank_solvable(index as u128, 3);
+            assert!(heuristic.estimate(&state) <= *distance as i32, "{:?} is {} slides from the goal", state, distance);
+        }
+    }
+
+    #[test]
+    fn solutions_have_the_fewest_slides() {
+        let goal = Board::goal_board(3);
+        let distances = slide_distances();
+
+        for index in (0..distances.len()).step_by(499) {
+            let board = Board::new(3, unrank_solvable(index as u128, 3));
+            let metrics = slide_tile_solver_with_metrics(&board, &goal);
+
+            assert!(board.apply_moves(&metrics.path).is_some_and(|end| end == goal));
+            assert_eq!(metrics.cost_of_path, distances[index] as usize, "{:?}", board.state);
+            assert_eq!(Slide::moves_to_slides(&metrics.path).len(), metrics.cost_of_path);
+        }
+    }
+}
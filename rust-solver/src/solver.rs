@@ -0,0 +1,190 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::{Board, CancelToken, Metrics, Progress, SessionStatus, SolverSession};
+use super::heuristics::HeuristicKind;
+use super::limits::{Limit, SearchLimits};
+use super::pdb::AdditivePatterns;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    // A* from both ends at once. Paths are at most epsilon times the optimal length
+    Bidirectional,
+
+    // Iterative deepening A*, optimal but slow on hard boards
+    IdaStar,
+
+    // Iterative deepening A* that counts sliding a whole line of tiles as one move, optimal
+    // in that (slide-tile) metric. Ignores the heuristic setting
+    SlideTileIdaStar,
+
+    // A* with the heuristic scaled up by epsilon. Solutions are at most epsilon times the optimal
+    // length, larger epsilon searches fewer nodes
+    WeightedAStar,
+
+    // Breadth first search, optimal without a heuristic. Stores every board it reaches, so only
+    // for small boards, meant as a baseline for checking other solvers. Ignores the heuristic
+    Bfs,
+
+    // Breadth first search from both ends, optimal and stores far fewer boards than Bfs
+    BidirectionalBfs,
+
+    // Optimal A* that stores only the frontier, in a fraction of the memory. Boards up to 5x5
+    FrontierAStar,
+
+    // Iterative deepening A* estimating with additive pattern databases (SolverConfig::patterns)
+    // instead of the heuristic setting. Optimal, and what it takes to solve random 5x5 boards
+    PatternIdaStar,
+
+    // Places the board a row and a column at a time, the way people solve it, then solves the
+    // last 3x3 corner optimally. Any size in polynomial time, but paths are far from optimal.
+    // Needs the goal's blank in that corner. Ignores the heuristic setting
+    Reduction
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "bidirectional"     => Some(Algorithm::Bidirectional),
+            "ida-star"          => Some(Algorithm::IdaStar),
+            "ida-star-stm"      => Some(Algorithm::SlideTileIdaStar),
+            "weighted-a-star"   => Some(Algorithm::WeightedAStar),
+            "bfs"               => Some(Algorithm::Bfs),
+            "bidirectional-bfs" => Some(Algorithm::BidirectionalBfs),
+            "frontier-a-star"   => Some(Algorithm::FrontierAStar),
+            "pattern-ida-star"  => Some(Algorithm::PatternIdaStar),
+            "reduction"         => Some(Algorithm::Reduction),
+            _ => None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Bidirectional    => "bidirectional",
+            Algorithm::IdaStar          => "ida-star",
+            Algorithm::SlideTileIdaStar => "ida-star-stm",
+            Algorithm::WeightedAStar    => "weighted-a-star",
+            Algorithm::Bfs              => "bfs",
+            Algorithm::BidirectionalBfs => "bidirectional-bfs",
+            Algorithm::FrontierAStar    => "frontier-a-star",
+            Algorithm::PatternIdaStar   => "pattern-ida-star",
+            Algorithm::Reduction        => "reduction"
+        }
+    }
+}
+
+// Used by the bidirectional search and WeightedAStar when no epsilon is given. With linear
+// conflicts this solves random 4x4 boards in milliseconds
+pub const DEFAULT_EPSILON: f64 = 2.5;
+
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub algorithm: Algorithm,
+    pub heuristic: HeuristicKind,
+
+    // Bidirectional and weighted A* find paths no longer than epsilon * optimal, values below 1
+    // are treated as 1
+    pub epsilon: f64,
+
+    pub limits: SearchLimits,
+
+    // Stops the search early once cancelled, see CancelToken
+    pub cancel: Option<CancelToken>,
+
+    // Pattern databases for PatternIdaStar, shared as they can run to hundreds of megabytes
+    pub patterns: Option<Arc<AdditivePatterns>>
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig {
+            algorithm: Algorithm::Bidirectional,
+            heuristic: HeuristicKind::LinearConflict,
+            epsilon: DEFAULT_EPSILON,
+            limits: SearchLimits::default(),
+            cancel: None,
+            patterns: None
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    // The start or goal isn't a valid layout, or they are different sizes
+    InvalidBoard(String),
+
+    // The goal can't be reached from the start
+    Unsolvable,
+
+    // A distance or macro table (ex. one loaded from a file) doesn't hold what it should
+    InvalidTable(String),
+
+    // A search limit was reached first. Holds the metrics so far, with the best partial path
+    BudgetExhausted(Limit, Box<Metrics>),
+
+    // The cancel token was set first. Holds the metrics so far, with the best partial path
+    Cancelled(Box<Metrics>)
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::InvalidBoard(reason) => write!(f, "invalid board: {}", reason),
+            SolveError::Unsolvable => write!(f, "the goal board can't be reached from the start board"),
+            SolveError::InvalidTable(reason) => write!(f, "invalid table: {}", reason),
+            SolveError::BudgetExhausted(limit, metrics) => write!(f, "the search reached its {} limit after {} nodes", limit, metrics.nodes_expanded),
+            SolveError::Cancelled(metrics) => write!(f, "the search was cancelled after {} nodes", metrics.nodes_expanded)
+        }
+    }
+}
+
+// Checks the boards and runs the configured algorithm. Unlike the individual solver functions
+// this never panics or loops forever on bad input
+pub fn solve(start_board: &Board, goal_board: &Board, config: &SolverConfig) -> Result<Metrics, SolveError> {
+    SolverSession::new(start_board, goal_board, config)?.run()
+}
+
+// Like solve, calling on_progress every interval_nodes nodes until the search stops
+pub fn solve_with_progress<F>(start_board: &Board, goal_board: &Board, config: &SolverConfig, interval_nodes: usize, mut on_progress: F) -> Result<Metrics, SolveError>
+    where F: FnMut(&Progress)
+{
+    let mut session = SolverSession::new(start_board, goal_board, config)?;
+
+    while let SessionStatus::Running = session.step(interval_nodes.max(1)) {
+        on_progress(&session.progress());
+    }
+    session.run()
+}
+
+pub(crate) fn check_boards(start_board: &Board, goal_board: &Board) -> Result<(), SolveError> {
+    start_board.validate().map_err(SolveError::InvalidBoard)?;
+    goal_board.validate().map_err(SolveError::InvalidBoard)?;
+
+    if start_board.size != goal_board.size {
+        return Err(SolveError::InvalidBoard(format!("start is {}x{} but goal is {}x{}", start_board.size, start_board.size, goal_board.size, goal_board.size)));
+    }
+    if !start_board.can_reach(goal_board) {
+        return Err(SolveError::Unsolvable);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_reported_while_solving() {
+        let start = Board::new(4, vec![11, 15, 3, 12, 2, 8, 10, 1, 4, 6, 5, 14, 13, 7, 9, 0]);
+        let goal = Board::goal_board(4);
+
+        let mut reports: Vec<Progress> = Vec::new();
+        let metrics = solve_with_progress(&start, &goal, &SolverConfig::default(), 100, |progress| reports.push(progress.clone())).unwrap();
+
+        assert!(!reports.is_empty());
+        assert!(reports.windows(2).all(|pair| pair[0].nodes_expanded < pair[1].nodes_expanded));
+        assert!(reports.iter().all(|progress| progress.nodes_expanded <= metrics.nodes_expanded));
+        assert_eq!(reports[0].frontier_sizes.len(), 2);
+        assert_eq!(start.apply_moves(&metrics.path).unwrap().state, goal.state);
+    }
+}
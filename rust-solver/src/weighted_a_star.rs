@@ -0,0 +1,175 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{Board, Move};
+use super::heuristics::{GoalDistance, HeuristicKind};
+use super::search::{Search, SearchStatus};
+
+const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+// A* ordered by f = g + epsilon * h, where g is the number of moves made and h an admissible
+// estimate of the moves left. Any solution it returns is at most epsilon times longer than the
+// optimal one, epsilon = 1 is plain (optimal) A*. Boards aren't re-expanded when a cheaper way
+// to them turns up, which keeps the node count down without losing the bound
+pub(crate) struct WeightedAStar {
+    size: usize,
+    goal: Vec<u8>,
+    heuristic: GoalDistance,
+
+    // Epsilon in thousandths, so the open list can use integer keys
+    weight: i64,
+
+    // Ordered by weighted f, then deepest first. Entries go stale when a cheaper way to a board
+    // is found, they are skipped when popped
+    open: BinaryHeap<(Reverse<i64>, i32, Vec<u8>)>,
+
+    // Cheapest known cost of each board reached, the move that got there and whether it has
+    // been expanded
+    reached: HashMap<Vec<u8>, (i32, Move, bool)>,
+
+    // The reached board with the smallest estimate, and that estimate
+    closest: (i32, Vec<u8>),
+
+    nodes_expanded: usize
+}
+
+impl WeightedAStar {
+    pub(crate) fn new(start_board: &Board, goal_board: &Board, kind: HeuristicKind, epsilon: f64) -> WeightedAStar {
+        let mut search = WeightedAStar {
+            size: start_board.size,
+            goal: goal_board.state.clone(),
+            heuristic: GoalDistance::with_kind(goal_board, kind),
+            weight: (epsilon.max(1.0) * 1000.0).round() as i64,
+            open: BinaryHeap::new(),
+            reached: HashMap::new(),
+            closest: (i32::MAX, Vec::new()),
+            nodes_expanded: 0
+        };
+
+        search.push(start_board.state.clone(), 0, Move::None);
+        search
+    }
+
+    fn push(&mut self, state: Vec<u8>, g: i32, came_by: Move) {
+        let h = self.heuristic.estimate(&state);
+        if h < self.closest.0 {
+            self.closest = (h, state.clone());
+        }
+
+        let f = 1000 * g as i64 + self.weight * h as i64;
+
+        self.reached.insert(state.clone(), (g, came_by, false));
+        self.open.push((Reverse(f), g, state));
+    }
+
+    // Walk back from the given board using the move that reached each board
+    fn trace_path(&self, state: &[u8]) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut board = Board::new(self.size, state.to_vec());
+
+        loop {
+            let came_by = self.reached[&board.state].1;
+            if came_by == Move::None {
+                break;
+            }
+            path.push(came_by);
+            board = board.apply_move(came_by.inverse()).expect("reached boards are linked by legal moves");
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+impl Search for WeightedAStar {
+    // Exhausted once the open list runs empty, which only happens if the goal can't be reached
+    fn step(&mut self, budget: usize) -> SearchStatus {
+        let target = self.nodes_expanded.saturating_add(budget);
+
+        while self.nodes_expanded < target {
+            let (_, g, state) = match self.open.pop() {
+                Some(entry) => entry,
+                None => return SearchStatus::Exhausted
+            };
+
+            let (known_g, came_by, expanded) = self.reached[&state];
+            if expanded || known_g < g {
+                continue;
+            }
+            if state == self.goal {
+                return SearchStatus::Solved(self.trace_path(&state));
+            }
+
+            self.reached.get_mut(&state).unwrap().2 = true;
+            self.nodes_expanded += 1;
+
+            let board = Board::new(self.size, state);
+            for the_move in MOVES.iter() {
+                if *the_move == came_by.inverse() {
+                    continue;
+                }
+                if let Some(child) = board.apply_move(*the_move) {
+                    if self.reached.get(&child.state).is_some_and(|(child_g, _, expanded)| *expanded || *child_g <= g + 1) {
+                        continue;
+                    }
+                    self.push(child.state, g + 1, *the_move);
+                }
+            }
+        }
+
+        SearchStatus::InProgress
+    }
+
+    fn nodes_expanded(&self) -> usize {
+        self.nodes_expanded
+    }
+
+    fn stored_states(&self) -> usize {
+        self.reached.len()
+    }
+
+    fn f_bound(&self) -> Option<usize> {
+        self.open.peek().map(|(_, g, state)| (g + self.heuristic.estimate(state)) as usize)
+    }
+
+    fn frontier_sizes(&self) -> Vec<usize> {
+        vec![self.open.len()]
+    }
+
+    fn best_path(&self) -> Vec<Move> {
+        self.trace_path(&self.closest.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Algorithm, SolverConfig, solve};
+    use crate::analysis::DistanceTable;
+
+    #[test]
+    fn paths_are_within_epsilon_of_optimal() {
+        let goal = Board::goal_board(3);
+        let table = DistanceTable::build(&goal).unwrap();
+
+        for epsilon in [1.0, 1.5, 2.5].iter() {
+            let config = SolverConfig { algorithm: Algorithm::WeightedAStar, epsilon: *epsilon, ..SolverConfig::default() };
+            for index in (0..table.board_count()).step_by(499) {
+                let board = Board::new(3, crate::ranking::unrank_solvable(index as u128, 3));
+                let optimal = table.distance(&board).unwrap();
+
+                let path = solve(&board, &goal, &config).unwrap().path;
+                assert_eq!(board.apply_moves(&path).unwrap().state, goal.state);
+                assert!(path.len() as f64 <= epsilon * optimal as f64, "{:?}: {} moves at epsilon {}, optimal {}", board.state, path.len(), epsilon, optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_exhaust_the_search() {
+        let start = Board::new(2, vec![2, 1, 3, 0]);
+        let mut search = WeightedAStar::new(&start, &Board::goal_board(2), HeuristicKind::LinearConflict, 1.0);
+        assert!(matches!(search.step(usize::MAX), SearchStatus::Exhausted));
+        assert!(matches!(search.step(usize::MAX), SearchStatus::Exhausted));
+    }
+}
@@ -0,0 +1,19 @@
+use std::env;
+use std::path::Path;
+
+use slider_solver_lib::Board;
+use slider_solver_lib::analysis::DistanceTable;
+
+// With the lookup-table feature, writes the standard 3x3 goal's distance table to OUT_DIR for
+// src/lookup.rs to include
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if env::var_os("CARGO_FEATURE_LOOKUP_TABLE").is_none() {
+        return;
+    }
+
+    let table = DistanceTable::build(&Board::goal_board(3)).expect("the standard goal is valid");
+    let path = Path::new(&env::var("OUT_DIR").expect("cargo sets OUT_DIR")).join("distances-3x3.bin");
+    table.save(path.to_str().expect("OUT_DIR is valid unicode")).expect("unable to write the distance table");
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use slider_solver_lib::{DEFAULT_ANYTIME_WEIGHT, Improvement};
+use wasm_bindgen::prelude::*;
+
+use crate::options::SolveOptions;
+use crate::results::{SolveFailure, encode_moves};
+
+// A solution from solve_anytime and how far from optimal it might be
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct AnytimeSolution {
+    moves: Vec<u8>,
+    pub cost: usize,
+
+    // No solution can be shorter than this, cost == lower_bound means the moves are optimal
+    pub lower_bound: usize,
+    pub nodes_expanded: usize,
+    pub running_time_ms: f64
+}
+
+#[wasm_bindgen]
+impl AnytimeSolution {
+    #[wasm_bindgen(getter)]
+    pub fn moves(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.moves[..])
+    }
+}
+
+impl From<&Improvement> for AnytimeSolution {
+    fn from(improvement: &Improvement) -> AnytimeSolution {
+        AnytimeSolution {
+            moves: encode_moves(&improvement.path),
+            cost: improvement.cost(),
+            lower_bound: improvement.lower_bound,
+            nodes_expanded: improvement.nodes_expanded,
+            running_time_ms: improvement.running_time.as_secs_f64() * 1000.0
+        }
+    }
+}
+
+// Finds a solution quickly and keeps shortening it for up to time_budget_ms. Every shorter
+// solution is passed to on_improvement, which returns true to keep looking. Takes the same options
+// object as solve_with_options and throws a SolveFailure if it's invalid. The heuristic, epsilon
+// (3 if not given) and limits are used, the algorithm is ignored. Returns the best solution, or
+// undefined if the search stopped before one was found.
+//
+// Runs to completion before returning, so call it from a Web Worker
+#[wasm_bindgen]
+pub fn solve_anytime(options: &JsValue, time_budget_ms: f64, on_improvement: &js_sys::Function) -> Result<Option<AnytimeSolution>, SolveFailure> {
+    crate::utils::set_panic_hook();
+
+    // The anytime search starts greedier than the others unless told otherwise
+    let epsilon_given = js_sys::Reflect::get(options, &JsValue::from_str("epsilon")).is_ok_and(|epsilon| !epsilon.is_undefined() && !epsilon.is_null());
+
+    let mut options = SolveOptions::from_js(options).map_err(SolveFailure::invalid_options)?;
+    let time_budget = Duration::from_secs_f64(time_budget_ms.max(0.0) / 1000.0);
+
+    let limits = &mut options.config.limits;
+    limits.time_limit = Some(limits.time_limit.map_or(time_budget, |limit| limit.min(time_budget)));
+    if !epsilon_given {
+        options.config.epsilon = DEFAULT_ANYTIME_WEIGHT;
+    }
+
+    let best = slider_solver_lib::anytime_solver(&options.start, &options.goal, &options.config, |improvement| {
+        let solution = AnytimeSolution::from(improvement);
+        match on_improvement.call1(&JsValue::NULL, &JsValue::from(solution)) {
+            Ok(keep_going) => keep_going.is_truthy(),
+            Err(_) => false
+        }
+    })?;
+
+    Ok(best.as_ref().map(AnytimeSolution::from))
+}
@@ -0,0 +1,110 @@
+use slider_solver_lib::Move;
+use wasm_bindgen::prelude::*;
+
+use crate::options::SolveOptions;
+use crate::results::{SolveFailure, encode_moves};
+
+// A game in progress, timed with Date.now(). ex.
+//   const game = new GameSession({ size: 4, tiles: [...] });
+//   game.click_tile(index);   // slides the tile (and any between it and the blank) if it can move
+//   if (game.solved) { ... game.elapsed_ms, game.move_count ... }
+#[wasm_bindgen]
+pub struct GameSession {
+    session: slider_solver_lib::GameSession
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    // Takes the same options object as solve_with_options (algorithm and heuristic are ignored),
+    // throws a SolveFailure if it's invalid or can't be solved
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &JsValue) -> Result<GameSession, SolveFailure> {
+        crate::utils::set_panic_hook();
+
+        let options = SolveOptions::from_js(options).map_err(SolveFailure::invalid_options)?;
+        let session = slider_solver_lib::GameSession::new(&options.start, &options.goal)?;
+
+        Ok(GameSession { session })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.session.board().size
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tiles(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.session.board().state[..])
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn start_tiles(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.session.start().state[..])
+    }
+
+    // Moves made so far, coded like solution moves
+    #[wasm_bindgen(getter)]
+    pub fn history(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&encode_moves(self.session.history())[..])
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn move_count(&self) -> usize {
+        self.session.move_count()
+    }
+
+    // Move count in the slide-tile metric, sliding a line of tiles counts once
+    #[wasm_bindgen(getter)]
+    pub fn slide_count(&self) -> usize {
+        self.session.slide_count()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn solved(&self) -> bool {
+        self.session.is_solved()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn can_undo(&self) -> bool {
+        self.session.can_undo()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn can_redo(&self) -> bool {
+        self.session.can_redo()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.session.elapsed_ms(js_sys::Date::now())
+    }
+
+    // Returns the number of moves made, 0 if the tile can't move
+    pub fn click_tile(&mut self, index: usize) -> usize {
+        self.session.click_tile(index, js_sys::Date::now())
+    }
+
+    // Moves the blank square, coded like solution moves (0 up, 1 down, 2 left, 3 right)
+    pub fn apply_move(&mut self, code: u8) -> bool {
+        let the_move = match code {
+            0 => Move::Up,
+            1 => Move::Down,
+            2 => Move::Left,
+            3 => Move::Right,
+            _ => return false
+        };
+        self.session.apply_move(the_move, js_sys::Date::now())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.session.undo(js_sys::Date::now())
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.session.redo(js_sys::Date::now())
+    }
+
+    pub fn reset(&mut self) {
+        self.session.reset();
+    }
+}
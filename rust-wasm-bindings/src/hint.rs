@@ -0,0 +1,41 @@
+use slider_solver_lib::DEFAULT_HINT_BUDGET;
+use wasm_bindgen::prelude::*;
+
+use crate::options::SolveOptions;
+use crate::results::{SolveFailure, encode_moves};
+
+// The suggested next move, coded the same way as solution moves (0 up, 1 down, 2 left, 3 right)
+#[wasm_bindgen]
+#[derive(Copy, Clone)]
+pub struct BoardHint {
+    best_move: Option<u8>,
+
+    // Exact when the board was solved optimally, otherwise a lower bound
+    pub distance: usize,
+    pub exact: bool
+}
+
+#[wasm_bindgen]
+impl BoardHint {
+    // Undefined when the board is already solved
+    #[wasm_bindgen(getter)]
+    pub fn best_move(&self) -> Option<u8> {
+        self.best_move
+    }
+}
+
+// Takes the same options object as solve_with_options (algorithm and heuristic are ignored), throws
+// a SolveFailure if it's invalid. Quick enough to call after every player move
+#[wasm_bindgen]
+pub fn hint(options: &JsValue) -> Result<BoardHint, SolveFailure> {
+    crate::utils::set_panic_hook();
+
+    let options = SolveOptions::from_js(options).map_err(SolveFailure::invalid_options)?;
+    let hint = slider_solver_lib::hint(&options.start, &options.goal, DEFAULT_HINT_BUDGET)?;
+
+    Ok(BoardHint {
+        best_move: encode_moves(&[hint.best_move]).first().copied(),
+        distance: hint.distance,
+        exact: hint.exact
+    })
+}
@@ -1,8 +1,29 @@
-use slider_solver_lib::{Board, Move, bidirectional_solver};
+use slider_solver_lib::{Board, Difficulty, Metrics, SessionStatus, SolveError, SolverConfig, MIN_BOARD_SIZE, MAX_BOARD_SIZE};
 use wasm_bindgen::prelude::*;
 
 mod utils;
 
+mod options;
+use options::SolveOptions;
+
+mod results;
+pub use results::{ErrorKind, SolveFailure, SolveMetrics, SolveResult};
+
+mod anytime;
+pub use anytime::{AnytimeSolution, solve_anytime};
+
+mod game;
+pub use game::GameSession;
+
+mod hint;
+pub use hint::{BoardHint, hint};
+
+mod session;
+pub use session::{SessionProgress, SolverSession};
+
+mod lookup;
+pub use lookup::solve_lookup;
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
@@ -12,64 +33,139 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 extern crate web_sys;
 
 // A macro to provide `println!(..)`-style syntax for `console.log` logging.
+#[allow(unused_macros)]
 macro_rules! console_log {
     ( $( $t:tt )* ) => {
         web_sys::console::log_1(&format!( $( $t )* ).into());
     }
 }
 
+// Solves a 4x4 board with the default solver. Throws if the board isn't valid or solvable,
+// use solve_with_options for other sizes, goals or algorithms
 #[wasm_bindgen]
-pub fn solve(state: js_sys::Uint8Array) -> js_sys::Uint8Array {
+pub fn solve(state: js_sys::Uint8Array) -> Result<js_sys::Uint8Array, JsValue> {
     utils::set_panic_hook();
 
+    let start = Board::new(4, state.to_vec());
+    let goal  = Board::goal_board(4);
 
-    if state.length() == 16 {
-        let mut native_state : [u8; 16] = [0; 16];
-        for ii in 0..16 as i32{
-            native_state[ii as usize] = state.at(ii).unwrap();
-        }
+    let result = match slider_solver_lib::solve(&start, &goal, &SolverConfig::default()) {
+        Ok(metrics) => SolveResult::success(metrics),
+        Err(error)  => SolveResult::from_error(error)
+    };
+
+    match result.into_moves() {
+        Ok(moves)  => Ok(js_sys::Uint8Array::from(&moves[..])),
+        Err(error) => Err(JsValue::from_str(&error.message()))
+    }
+}
 
-        let start = Board {
-            size: 4,
-            state: native_state,
-            m_list: Vec::new(),
-            cost: 0,
-            score: 0
-        };
-
-        let end : [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
-        let goal = Board {
-            size: 4,
-            state: end,
-            m_list: Vec::new(),
-            cost: 0,
-            score: 0
-        };
-
-        let solution = bidirectional_solver(&start, &goal);
-
-        let mut native_solution : Vec<u8> = Vec::new();
-        for the_move in solution {
-            match the_move {
-                Move::Up    => native_solution.push(0),
-                Move::Down  => native_solution.push(1),
-                Move::Left  => native_solution.push(2),
-                Move::Right => native_solution.push(3),
-                Move::None  => ()
+// Solves the board described by a JS options object (see SolveOptions). Never throws, problems
+// are reported through the error on the returned result
+#[wasm_bindgen]
+pub fn solve_with_options(options: &JsValue) -> SolveResult {
+    utils::set_panic_hook();
+
+    let options = match SolveOptions::from_js(options) {
+        Ok(options) => options,
+        Err(message) => return SolveResult::failure(SolveFailure::invalid_options(message))
+    };
+
+    match solve_until_stopped(&options) {
+        Ok(metrics) => SolveResult::success(metrics),
+        Err(error)  => SolveResult::from_error(error)
+    }
+}
+
+// How many nodes are expanded between calls to options.should_stop and options.on_progress
+const CALLBACK_INTERVAL: usize = 4096;
+
+// Runs the solve a slice at a time when there are callbacks, cancelling it once should_stop
+// returns true (or throws). Errors thrown by on_progress are ignored
+fn solve_until_stopped(options: &SolveOptions) -> Result<Metrics, SolveError> {
+    let mut session = slider_solver_lib::SolverSession::new(&options.start, &options.goal, &options.config)?;
+
+    if options.should_stop.is_some() || options.on_progress.is_some() {
+        while let SessionStatus::Running = session.step(CALLBACK_INTERVAL) {
+            if let Some(on_progress) = &options.on_progress {
+                let _ = on_progress.call1(&JsValue::NULL, &SessionProgress::from_session(&session).into());
+            }
+            if let Some(should_stop) = &options.should_stop {
+                if should_stop.call0(&JsValue::NULL).map_or(true, |stop| stop.is_truthy()) {
+                    session.cancel();
+                }
             }
         }
+    }
+    session.run()
+}
+
+// Throws if the size isn't supported
+#[wasm_bindgen]
+pub fn generate(size: usize) -> Result<js_sys::Uint8Array, JsValue> {
+    check_size(size)?;
+    let board = Board::generate_board(size);
+
+    Ok(js_sys::Uint8Array::from(&board.state[..]))
+}
+
+// Same seed gives the same board here as from the command line 'generate --seed'
+#[wasm_bindgen]
+pub fn generate_seeded(size: usize, seed: u64) -> Result<js_sys::Uint8Array, JsValue> {
+    check_size(size)?;
+    let board = Board::generate_board_seeded(size, seed);
+
+    Ok(js_sys::Uint8Array::from(&board.state[..]))
+}
+
+// Generating a board of an unsupported size panics, which JS would only see as a trap
+fn check_size(size: usize) -> Result<(), JsValue> {
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        return Err(JsValue::from_str(&format!("board size must be between {} and {}, got {}", MIN_BOARD_SIZE, MAX_BOARD_SIZE, size)));
+    }
+    Ok(())
+}
 
-        js_sys::Uint8Array::from(&native_solution[..])
+// A generated board along with the length of its optimal solution
+#[wasm_bindgen]
+pub struct GeneratedBoard {
+    tiles: Vec<u8>,
+    optimal_moves: usize
+}
 
+#[wasm_bindgen]
+impl GeneratedBoard {
+    #[wasm_bindgen(getter)]
+    pub fn tiles(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.tiles[..])
     }
-    else {
-        js_sys::Uint8Array::new_with_length(1)
+
+    #[wasm_bindgen(getter)]
+    pub fn optimal_moves(&self) -> usize {
+        self.optimal_moves
     }
 }
 
+// A board whose optimal solution is min_moves..=max_moves long, undefined if none could be found
+// within the node budget (Board::generate_board_with_difficulty) or the size isn't supported
 #[wasm_bindgen]
-pub fn generate(size: usize) -> js_sys::Uint8Array {
-    let board = Board::generate_board(size);
+pub fn generate_with_moves(size: usize, min_moves: usize, max_moves: usize, seed: u64) -> Option<GeneratedBoard> {
+    check_size(size).ok()?;
+    let (board, optimal_moves) = Board::generate_board_with_difficulty_seeded(size, min_moves, max_moves, seed)?;
+
+    Some(GeneratedBoard { tiles: board.state, optimal_moves })
+}
+
+// difficulty is one of "easy", "medium" or "hard"
+#[wasm_bindgen]
+pub fn generate_with_difficulty(size: usize, difficulty: &str, seed: u64) -> Option<GeneratedBoard> {
+    let difficulty = match difficulty {
+        "easy"   => Difficulty::Easy,
+        "medium" => Difficulty::Medium,
+        "hard"   => Difficulty::Hard,
+        _ => return None
+    };
 
-    js_sys::Uint8Array::from(&board.state[..])
+    let (min_moves, max_moves) = difficulty.move_range(size);
+    generate_with_moves(size, min_moves, max_moves, seed)
 }
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use slider_solver_lib::{Board, Metrics, lookup_solver};
+use slider_solver_lib::analysis::{DistanceTable, MAX_ANALYSIS_SIZE};
+use wasm_bindgen::prelude::*;
+
+use crate::options::SolveOptions;
+use crate::results::{SolveFailure, SolveResult};
+
+#[cfg(feature = "lookup-table")]
+thread_local! {
+    // Written by build.rs, decoded the first time a 3x3 board is looked up
+    static EMBEDDED_3X3: DistanceTable = DistanceTable::from_bytes(include_bytes!(concat!(env!("OUT_DIR"), "/distances-3x3.bin")))
+        .expect("build.rs writes a valid table");
+}
+
+// Solves 2x2 and 3x3 boards optimally by reading the path off a distance table. Takes the same
+// options object as solve_with_options (algorithm, heuristic and the limits are ignored). The
+// standard 3x3 goal uses the table embedded at build time and answers in microseconds, other
+// goals (and 2x2) build a table first, which takes a fraction of a second for 3x3
+#[wasm_bindgen]
+pub fn solve_lookup(options: &JsValue) -> SolveResult {
+    crate::utils::set_panic_hook();
+
+    let options = match SolveOptions::from_js(options) {
+        Ok(options) => options,
+        Err(message) => return SolveResult::failure(SolveFailure::invalid_options(message))
+    };
+    if options.start.size > MAX_ANALYSIS_SIZE {
+        return SolveResult::failure(SolveFailure::invalid_options(format!("lookup tables only go up to {}x{} boards", MAX_ANALYSIS_SIZE, MAX_ANALYSIS_SIZE)));
+    }
+
+    let start_time = js_sys::Date::now();
+
+    #[cfg(feature = "lookup-table")]
+    {
+        if options.goal == Board::goal_board(3) {
+            return EMBEDDED_3X3.with(|table| lookup(table, &options.start, start_time));
+        }
+    }
+
+    match DistanceTable::build(&options.goal) {
+        Ok(table) => lookup(&table, &options.start, start_time),
+        Err(message) => SolveResult::failure(SolveFailure::invalid_options(message))
+    }
+}
+
+fn lookup(table: &DistanceTable, start: &Board, start_time: f64) -> SolveResult {
+    match lookup_solver(table, start) {
+        Ok(path) => SolveResult::success(Metrics {
+            cost_of_path: path.len(),
+            path,
+            nodes_expanded: 0,
+            running_time: Duration::from_secs_f64((js_sys::Date::now() - start_time).max(0.0) / 1000.0)
+        }),
+        Err(error) => SolveResult::from_error(error)
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use slider_solver_lib::{Algorithm, Board, HeuristicKind, SolverConfig};
+use wasm_bindgen::prelude::*;
+
+// Everything needed to run a solve, read from a JS object like
+// { size: 4, tiles: [...], goal: [...], algorithm: "ida-star", heuristic: "manhattan", epsilon: 1.5 }
+// Only tiles is required, size defaults to fitting the tiles, goal to the standard goal,
+// algorithm to "bidirectional", heuristic to "linear-conflict" and epsilon to 2.5. epsilon bounds
+// the path length of "bidirectional" and "weighted-a-star" as a multiple of the optimal length.
+// max_nodes, max_stored_states and time_limit_ms stop the search early, none are set by default.
+// time_limit_ms only counts time spent searching, not the time between SolverSession steps.
+// should_stop is a function solve_with_options calls every few thousand nodes, the search stops
+// once it returns true (ex. checking a flag a worker's owner can set). on_progress is called just
+// as often with a SessionProgress
+pub struct SolveOptions {
+    pub start: Board,
+    pub goal: Board,
+    pub config: SolverConfig,
+    pub should_stop: Option<js_sys::Function>,
+    pub on_progress: Option<js_sys::Function>
+}
+
+impl SolveOptions {
+    pub fn from_js(options: &JsValue) -> Result<SolveOptions, String> {
+        if !options.is_object() {
+            return Err("options must be an object".to_string());
+        }
+
+        let tiles = get_tiles(options, "tiles")?.ok_or_else(|| "options.tiles is required".to_string())?;
+
+        let size = match get_number(options, "size")? {
+            Some(size) => size as usize,
+            None => (tiles.len() as f64).sqrt() as usize
+        };
+
+        let start = Board::new(size, tiles);
+        let goal = match get_tiles(options, "goal")? {
+            Some(goal) => Board::new(size, goal),
+            None => Board::goal_board(size)
+        };
+
+        let mut config = SolverConfig::default();
+
+        if let Some(name) = get_string(options, "algorithm")? {
+            config.algorithm = Algorithm::from_name(&name).ok_or_else(|| format!("unknown algorithm '{}'", name))?;
+        }
+        if let Some(name) = get_string(options, "heuristic")? {
+            config.heuristic = HeuristicKind::from_name(&name).ok_or_else(|| format!("unknown heuristic '{}'", name))?;
+        }
+
+        if let Some(epsilon) = get_number(options, "epsilon")? {
+            config.epsilon = epsilon;
+        }
+
+        config.limits.max_nodes = get_number(options, "max_nodes")?.map(|nodes| nodes as usize);
+        config.limits.max_stored_states = get_number(options, "max_stored_states")?.map(|states| states as usize);
+        config.limits.time_limit = get_number(options, "time_limit_ms")?.map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+        let should_stop = get_function(options, "should_stop")?;
+        let on_progress = get_function(options, "on_progress")?;
+
+        Ok(SolveOptions { start, goal, config, should_stop, on_progress })
+    }
+}
+
+fn get(options: &JsValue, key: &str) -> Result<Option<JsValue>, String> {
+    let value = js_sys::Reflect::get(options, &JsValue::from_str(key)).map_err(|_| format!("unable to read options.{}", key))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(value))
+}
+
+fn get_number(options: &JsValue, key: &str) -> Result<Option<f64>, String> {
+    match get(options, key)? {
+        Some(value) => value.as_f64().map(Some).ok_or_else(|| format!("options.{} must be a number", key)),
+        None => Ok(None)
+    }
+}
+
+fn get_string(options: &JsValue, key: &str) -> Result<Option<String>, String> {
+    match get(options, key)? {
+        Some(value) => value.as_string().map(Some).ok_or_else(|| format!("options.{} must be a string", key)),
+        None => Ok(None)
+    }
+}
+
+fn get_function(options: &JsValue, key: &str) -> Result<Option<js_sys::Function>, String> {
+    match get(options, key)? {
+        Some(value) => value.dyn_into::<js_sys::Function>().map(Some).map_err(|_| format!("options.{} must be a function", key)),
+        None => Ok(None)
+    }
+}
+
+// Accepts a plain array of numbers or a Uint8Array
+fn get_tiles(options: &JsValue, key: &str) -> Result<Option<Vec<u8>>, String> {
+    match get(options, key)? {
+        Some(value) => {
+            if !value.is_object() {
+                return Err(format!("options.{} must be an array of tiles", key));
+            }
+            Ok(Some(js_sys::Uint8Array::new(&value).to_vec()))
+        },
+        None => Ok(None)
+    }
+}
@@ -0,0 +1,167 @@
+use slider_solver_lib::{Metrics, Move, SolveError};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    // The options object was missing something or had the wrong type
+    InvalidOptions,
+
+    // The tiles (or goal) aren't a valid board
+    InvalidBoard,
+
+    // The goal can't be reached from the start
+    Unsolvable,
+
+    // The lookup table doesn't hold what it should
+    InvalidTable,
+
+    // The search hit one of the limits in the options before finishing
+    BudgetExhausted,
+
+    // options.should_stop returned true before the search finished
+    Cancelled
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SolveFailure {
+    kind: ErrorKind,
+    message: String
+}
+
+#[wasm_bindgen]
+impl SolveFailure {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl SolveFailure {
+    pub fn invalid_options(message: String) -> SolveFailure {
+        SolveFailure { kind: ErrorKind::InvalidOptions, message }
+    }
+}
+
+impl From<SolveError> for SolveFailure {
+    fn from(error: SolveError) -> SolveFailure {
+        let kind = match error {
+            SolveError::InvalidBoard(_) => ErrorKind::InvalidBoard,
+            SolveError::Unsolvable      => ErrorKind::Unsolvable,
+            SolveError::InvalidTable(_) => ErrorKind::InvalidTable,
+            SolveError::BudgetExhausted(..) => ErrorKind::BudgetExhausted,
+            SolveError::Cancelled(_)        => ErrorKind::Cancelled
+        };
+        SolveFailure { kind, message: error.to_string() }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone)]
+pub struct SolveMetrics {
+    pub cost_of_path: usize,
+    pub nodes_expanded: usize,
+    pub running_time_ms: f64
+}
+
+impl From<&Metrics> for SolveMetrics {
+    fn from(metrics: &Metrics) -> SolveMetrics {
+        SolveMetrics {
+            cost_of_path: metrics.cost_of_path,
+            nodes_expanded: metrics.nodes_expanded,
+            running_time_ms: metrics.running_time.as_secs_f64() * 1000.0
+        }
+    }
+}
+
+// Outcome of solve_with_options. Solved results have metrics and no error. When the search hits a
+// limit or is cancelled, error is set and moves and metrics describe the partial path to the
+// closest board it found. Otherwise moves is empty on error
+#[wasm_bindgen]
+pub struct SolveResult {
+    moves: Vec<u8>,
+    metrics: Option<SolveMetrics>,
+    error: Option<SolveFailure>
+}
+
+#[wasm_bindgen]
+impl SolveResult {
+    // Moves of the blank square, 0 - Up, 1 - Down, 2 - Left, 3 - Right
+    #[wasm_bindgen(getter)]
+    pub fn moves(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.moves[..])
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn metrics(&self) -> Option<SolveMetrics> {
+        self.metrics
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<SolveFailure> {
+        self.error.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn solved(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl SolveResult {
+    pub fn success(metrics: Metrics) -> SolveResult {
+        SolveResult {
+            moves: encode_moves(&metrics.path),
+            metrics: Some(SolveMetrics::from(&metrics)),
+            error: None
+        }
+    }
+
+    // Keeps the partial path when the search ran out of budget or was cancelled
+    pub fn from_error(error: SolveError) -> SolveResult {
+        let partial = match &error {
+            SolveError::BudgetExhausted(_, metrics) | SolveError::Cancelled(metrics) => Some(metrics.as_ref().clone()),
+            _ => None
+        };
+
+        match partial {
+            Some(metrics) => SolveResult {
+                moves: encode_moves(&metrics.path),
+                metrics: Some(SolveMetrics::from(&metrics)),
+                error: Some(error.into())
+            },
+            None => SolveResult::failure(error.into())
+        }
+    }
+
+    pub fn failure(error: SolveFailure) -> SolveResult {
+        SolveResult { moves: Vec::new(), metrics: None, error: Some(error) }
+    }
+
+    pub fn into_moves(self) -> Result<Vec<u8>, SolveFailure> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.moves)
+        }
+    }
+}
+
+pub fn encode_moves(moves: &[Move]) -> Vec<u8> {
+    let mut native_solution : Vec<u8> = Vec::new();
+    for the_move in moves {
+        match the_move {
+            Move::Up    => native_solution.push(0),
+            Move::Down  => native_solution.push(1),
+            Move::Left  => native_solution.push(2),
+            Move::Right => native_solution.push(3),
+            Move::None  => ()
+        }
+    }
+    native_solution
+}
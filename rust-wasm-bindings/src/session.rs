@@ -0,0 +1,110 @@
+use slider_solver_lib::{SessionStatus, SolveError};
+use wasm_bindgen::prelude::*;
+
+use crate::options::SolveOptions;
+use crate::results::{SolveFailure, SolveResult};
+
+// Where a session got to after a step
+#[wasm_bindgen]
+#[derive(Copy, Clone)]
+pub struct SessionProgress {
+    // True once the session is solved, cancelled, exhausted or unsolvable, stepping again does nothing
+    pub done: bool,
+    pub solved: bool,
+    pub cancelled: bool,
+
+    // The search hit one of the limits in the options before finishing
+    pub exhausted: bool,
+
+    // The search ran out of boards without reaching the goal
+    pub unsolvable: bool,
+    pub nodes_expanded: usize,
+    pub stored_states: usize,
+
+    // IDA*'s current threshold or the f at the front of the open list, undefined once there is
+    // nothing left to search. Bidirectional searches give the lower bound they are working to meet
+    pub f_bound: Option<usize>,
+
+    // Boards waiting to be expanded. One directional searches only use forward_frontier, which
+    // for IDA* is the depth of the current path
+    pub forward_frontier: usize,
+    pub backward_frontier: usize,
+
+    pub running_time_ms: f64
+}
+
+impl SessionProgress {
+    pub fn from_session(session: &slider_solver_lib::SolverSession) -> SessionProgress {
+        let (solved, cancelled, exhausted, unsolvable) = match session.status() {
+            SessionStatus::Running         => (false, false, false, false),
+            SessionStatus::Solved(_)       => (true, false, false, false),
+            SessionStatus::Cancelled       => (false, true, false, false),
+            SessionStatus::Exhausted(_, _) => (false, false, true, false),
+            SessionStatus::Unsolvable      => (false, false, false, true)
+        };
+        let progress = session.progress();
+
+        SessionProgress {
+            done: solved || cancelled || exhausted || unsolvable,
+            solved,
+            cancelled,
+            exhausted,
+            unsolvable,
+            nodes_expanded: progress.nodes_expanded,
+            stored_states: progress.stored_states,
+            f_bound: progress.f_bound,
+            forward_frontier: progress.frontier_sizes.first().copied().unwrap_or(0),
+            backward_frontier: progress.frontier_sizes.get(1).copied().unwrap_or(0),
+            running_time_ms: progress.running_time.as_secs_f64() * 1000.0
+        }
+    }
+}
+
+// A solve that runs a slice at a time, ex.
+//   const session = new SolverSession({ tiles: [...] });
+//   let progress = session.step(20000);   // repeat from a worker or idle callback until progress.done
+//   const moves = session.result().moves;
+#[wasm_bindgen]
+pub struct SolverSession {
+    session: slider_solver_lib::SolverSession
+}
+
+#[wasm_bindgen]
+impl SolverSession {
+    // Takes the same options object as solve_with_options, throws a SolveFailure if it's invalid
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &JsValue) -> Result<SolverSession, SolveFailure> {
+        crate::utils::set_panic_hook();
+
+        let options = SolveOptions::from_js(options).map_err(SolveFailure::invalid_options)?;
+        let session = slider_solver_lib::SolverSession::new(&options.start, &options.goal, &options.config)?;
+
+        Ok(SolverSession { session })
+    }
+
+    pub fn step(&mut self, budget_nodes: usize) -> SessionProgress {
+        self.session.step(budget_nodes);
+        self.progress()
+    }
+
+    pub fn cancel(&mut self) {
+        self.session.cancel();
+    }
+
+    pub fn progress(&self) -> SessionProgress {
+        SessionProgress::from_session(&self.session)
+    }
+
+    // The solution once the session is solved, the partial path once it's exhausted or the error
+    // once it's unsolvable, undefined until then
+    pub fn result(&self) -> Option<SolveResult> {
+        match self.session.status() {
+            SessionStatus::Solved(metrics) => Some(SolveResult::success(metrics.clone())),
+            SessionStatus::Exhausted(limit, metrics) => {
+                Some(SolveResult::from_error(SolveError::BudgetExhausted(*limit, Box::new(metrics.clone()))))
+            },
+            SessionStatus::Unsolvable => Some(SolveResult::from_error(SolveError::Unsolvable)),
+            _ => None
+        }
+    }
+}